@@ -40,13 +40,10 @@ fn main() {
             asset_issuance: elements::AssetIssuance::default(),
             witness: elements::TxInWitness::default(),
         }],
-        output: vec![elements::TxOut {
-            script_pubkey: elements::Script::new(),
-            value: elements::confidential::Value::Explicit(100_000_000),
-            witness: elements::TxOutWitness::default(),
-            asset: elements::confidential::Asset::default(),
-            nonce: elements::confidential::Nonce::default(),
-        }],
+        output: vec![miniscript::tx::fee_output(
+            elements::AssetId::default(),
+            100_000_000,
+        )],
     };
 
     #[cfg_attr(feature="cargo-fmt", rustfmt_skip)]
@@ -100,6 +100,18 @@ pub(super) fn verify_checksum(s: &str) -> Result<&str, Error> {
     Ok(desc_str)
 }
 
+/// Checks only the checksum of a descriptor string, without parsing
+/// the descriptor itself
+///
+/// Unlike parsing, which accepts descriptors without a checksum, this
+/// errors if the checksum is missing.
+pub fn verify_descriptor_checksum(s: &str) -> Result<(), Error> {
+    if !s.contains('#') {
+        return Err(Error::BadDescriptor("Missing checksum".to_owned()));
+    }
+    verify_checksum(s).map(|_| ())
+}
+
 /// Helper function to strip checksum without verifying
 #[allow(dead_code)]
 pub(super) fn strip_checksum(s: &str) -> &str {
@@ -139,6 +151,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_verify_descriptor_checksum() {
+        let desc = "elpkh(tpubD6NzVbkrYhZ4XHndKkuB8FifXm8r5FQHwrN6oZuWCz13qb93rtgKvD4PQsqC4HP4yhV3tA2fqr2RbY5mNXfM7RxXUoeABoDtsFUq2zJq6YK/44'/1'/0'/0/*)";
+        assert!(verify_descriptor_checksum(&format!("{}#g7zpd3we", desc)).is_ok());
+        assert_eq!(
+            verify_descriptor_checksum(&format!("{}#g7zpd3wf", desc))
+                .err()
+                .unwrap()
+                .to_string(),
+            "Invalid descriptor: Invalid checksum 'g7zpd3wf', expected 'g7zpd3we'"
+        );
+        assert_eq!(
+            verify_descriptor_checksum(desc).err().unwrap().to_string(),
+            "Invalid descriptor: Missing checksum"
+        );
+        // The descriptor body is not parsed
+        let bogus = "elpkh(not a key)";
+        let checksum = desc_checksum(bogus).unwrap();
+        assert!(verify_descriptor_checksum(&format!("{}#{}", bogus, checksum)).is_ok());
+    }
+
     #[test]
     fn test_desc_checksum_invalid_character() {
         let sparkle_heart = vec![240, 159, 146, 150];
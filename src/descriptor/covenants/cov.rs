@@ -68,6 +68,7 @@ use {
 use Extension;
 
 use super::super::{
+    check_nesting,
     checksum::{desc_checksum, verify_checksum},
     ElementsTrait, ELMTS_STR,
 };
@@ -300,6 +301,7 @@ where
         if top.name == "elcovwsh" && top.args.len() == 2 {
             let pk = expression::terminal(&top.args[0], |pk| Pk::from_str(pk))?;
             let top = &top.args[1];
+            check_nesting("elcovwsh", top.name, &[])?;
             let sub = Miniscript::from_tree(&top)?;
             Segwitv0::top_level_checks(&sub)?;
             Ok(CovenantDescriptor { pk: pk, ms: sub })
@@ -70,7 +70,10 @@ mod tests {
         AssetId, AssetIssuance, OutPoint, Script, SigHashType, Transaction, TxIn, TxInWitness,
         TxOut, Txid,
     };
+    use extensions::OutputConstraint;
     use interpreter::SatisfiedConstraint;
+    use miniscript::context::ScriptContextError;
+    use miniscript::limits::MAX_SCRIPT_ELEMENT_SIZE;
     use std::str::FromStr;
     use util::{count_non_push_opcodes, witness_size};
     use Interpreter;
@@ -98,6 +101,73 @@ mod tests {
         string_rtt("elcovwsh(A,outputs_pref(01020304))");
     }
 
+    #[test]
+    fn referenced_assets() {
+        let btc = AssetId::from_slice(&BTC_ASSET).unwrap();
+        let mut out = TxOut::default();
+        out.asset = confidential::Asset::Explicit(btc);
+        out.value = confidential::Value::Explicit(99_000);
+        let desc = Descriptor::<String>::from_str(&format!(
+            "elcovwsh(A,and_v(v:pk(B),outputs_pref({})))",
+            serialize(&out).to_hex(),
+        ))
+        .unwrap();
+        assert_eq!(desc.referenced_assets(), vec![btc]);
+
+        // Only a prefix of the asset
+        let desc = Descriptor::<String>::from_str("elcovwsh(A,outputs_pref(01020304))").unwrap();
+        assert!(desc.referenced_assets().is_empty());
+        let desc = Descriptor::<String>::from_str("elwsh(pk(A))").unwrap();
+        assert!(desc.referenced_assets().is_empty());
+    }
+
+    #[test]
+    fn output_constraints() {
+        let btc = AssetId::from_slice(&BTC_ASSET).unwrap();
+        let cold = TxOut {
+            asset: confidential::Asset::Explicit(btc),
+            value: confidential::Value::Explicit(99_000),
+            nonce: confidential::Nonce::Null,
+            script_pubkey: Script::new_v0_wsh(&Script::from(vec![0x51]).wscript_hash()),
+            witness: Default::default(),
+        };
+        // A vault which the hot key can only sweep to the cold output
+        let desc = Descriptor::<String>::from_str(&format!(
+            "elcovwsh(A,or_d(pk(Cold),and_v(v:pk(Hot),outputs_pref({}))))",
+            serialize(&cold).to_hex(),
+        ))
+        .unwrap();
+        assert_eq!(
+            desc.output_constraints(),
+            vec![OutputConstraint {
+                index: 0,
+                asset: Some(cold.asset),
+                value: Some(cold.value),
+                nonce: Some(cold.nonce),
+                script_pubkey: Some(cold.script_pubkey.clone()),
+            }]
+        );
+
+        // The second output is only pinned up to its value
+        let mut pref = serialize(&cold);
+        pref.extend(serialize(&cold.asset));
+        pref.extend(serialize(&cold.value));
+        pref.push(0x01);
+        let desc =
+            Descriptor::<String>::from_str(&format!("elcovwsh(A,outputs_pref({}))", pref.to_hex()))
+                .unwrap();
+        let constraints = desc.output_constraints();
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints[1].index, 1);
+        assert_eq!(constraints[1].asset, Some(cold.asset));
+        assert_eq!(constraints[1].value, Some(cold.value));
+        assert_eq!(constraints[1].nonce, None);
+        assert_eq!(constraints[1].script_pubkey, None);
+
+        let desc = Descriptor::<String>::from_str("elwsh(pk(A))").unwrap();
+        assert!(desc.output_constraints().is_empty());
+    }
+
     fn script_rtt(desc_str: &str) {
         let desc = Descriptor::<bitcoin::PublicKey>::from_str(desc_str).unwrap();
         assert_eq!(desc.desc_type(), DescriptorType::Cov);
@@ -340,6 +410,46 @@ mod tests {
         _satisfy_and_interpret(desc, sks[0]).unwrap_err();
     }
 
+    #[test]
+    fn new_cov_outputs() {
+        let (pks, sks) = setup_keys(5);
+        let mut out = TxOut::default();
+        out.script_pubkey = script::Builder::new()
+            .push_opcode(opcodes::all::OP_PUSHNUM_1)
+            .into_script()
+            .to_v0_p2wsh();
+        out.value = confidential::Value::Explicit(99_000);
+        out.asset = confidential::Asset::Explicit(AssetId::from_slice(&BTC_ASSET).unwrap());
+        let mut fee = TxOut::default();
+        fee.asset = out.asset;
+        fee.value = confidential::Value::Explicit(2_000);
+
+        let mut pref = serialize(&out);
+        pref.extend(serialize(&out));
+        let desc = Descriptor::new_cov_outputs(pks[0], &[out.clone(), out.clone()]).unwrap();
+        assert_eq!(
+            desc.to_string_no_chksum(),
+            format!("elcovwsh({},outputs_pref({}))", pks[0], pref.to_hex())
+        );
+        // The spend pays both outputs, followed by the fee
+        _satisfy_and_interpret(desc, sks[0]).unwrap();
+        let desc =
+            Descriptor::new_cov_outputs(pks[0], &[out.clone(), out.clone(), fee.clone()]).unwrap();
+        _satisfy_and_interpret(desc, sks[0]).unwrap();
+
+        // A spend to other outputs is rejected
+        let desc = Descriptor::new_cov_outputs(pks[0], &[out.clone(), fee.clone()]).unwrap();
+        _satisfy_and_interpret(desc, sks[0]).unwrap_err();
+
+        // Too many outputs to fit in a single stack element
+        let outs = vec![out; 10];
+        assert!(serialize(&outs[0]).len() * 10 > MAX_SCRIPT_ELEMENT_SIZE);
+        match Descriptor::new_cov_outputs(pks[0], &outs) {
+            Err(Error::ContextError(ScriptContextError::CovElementSizeExceeded)) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
     // Fund output and spend tx are tests handy with code for
     // running with regtest mode and testing that the scripts
     // are accepted by elementsd
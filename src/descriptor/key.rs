@@ -1,13 +1,13 @@
 use std::{error, fmt, str::FromStr};
 
-use bitcoin::{self, util::bip32, XpubIdentifier};
+use bitcoin::{self, hashes::hash160, util::bip32, XpubIdentifier};
 
 use elements::{
     hashes::hex::FromHex,
     hashes::Hash,
     secp256k1_zkp::{self, Secp256k1, Signing},
 };
-use MiniscriptKey;
+use {MiniscriptKey, ToPublicKey};
 
 /// The MiniscriptKey corresponding to Descriptors. This can
 /// either be Single public key or a Xpub
@@ -17,6 +17,8 @@ pub enum DescriptorPublicKey {
     SinglePub(DescriptorSinglePub),
     /// Xpub
     XPub(DescriptorXKey<bip32::ExtendedPubKey>),
+    /// Xpub with several derivation paths, as in BIP389 `<0;1>` steps
+    MultiXPub(DescriptorMultiXKey<bip32::ExtendedPubKey>),
 }
 
 /// A Single Descriptor Key with optional origin information
@@ -125,6 +127,20 @@ pub struct DescriptorXKey<K: InnerXKey> {
     pub wildcard: Wildcard,
 }
 
+/// Instance of an extended key with origin and several derivation paths,
+/// which differ in exactly one step (written `<a;b;...>`, see BIP389)
+#[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd, Hash)]
+pub struct DescriptorMultiXKey<K: InnerXKey> {
+    /// Origin information
+    pub origin: Option<(bip32::Fingerprint, bip32::DerivationPath)>,
+    /// The extended key
+    pub xkey: K,
+    /// The derivation paths, at least two, all of the same length
+    pub derivation_paths: Vec<bip32::DerivationPath>,
+    /// Whether the descriptor is wildcard
+    pub wildcard: Wildcard,
+}
+
 impl DescriptorSinglePriv {
     /// Returns the public key of this key
     fn as_public<C: Signing>(
@@ -223,6 +239,17 @@ impl fmt::Display for DescriptorPublicKey {
                 }
                 Ok(())
             }
+            DescriptorPublicKey::MultiXPub(ref xpub) => {
+                maybe_fmt_master_id(f, &xpub.origin)?;
+                xpub.xkey.fmt(f)?;
+                fmt_multi_derivation_paths(f, &xpub.derivation_paths)?;
+                match xpub.wildcard {
+                    Wildcard::None => {}
+                    Wildcard::Unhardened => write!(f, "/*")?,
+                    Wildcard::Hardened => write!(f, "/*h")?,
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -275,6 +302,29 @@ fn fmt_derivation_path(f: &mut fmt::Formatter, path: &bip32::DerivationPath) ->
     Ok(())
 }
 
+/// Writes several derivation paths of the same length to the formatter,
+/// collapsing the steps on which they differ into a `<a;b;...>` step
+fn fmt_multi_derivation_paths(
+    f: &mut fmt::Formatter,
+    paths: &[bip32::DerivationPath],
+) -> fmt::Result {
+    for (i, child) in (&paths[0]).into_iter().enumerate() {
+        if paths.iter().all(|p| p[i] == *child) {
+            write!(f, "/{}", child)?;
+        } else {
+            fmt::Formatter::write_str(f, "/<")?;
+            for (n, p) in paths.iter().enumerate() {
+                if n > 0 {
+                    fmt::Formatter::write_str(f, ";")?;
+                }
+                write!(f, "{}", p[i])?;
+            }
+            fmt::Formatter::write_str(f, ">")?;
+        }
+    }
+    Ok(())
+}
+
 impl FromStr for DescriptorPublicKey {
     type Err = DescriptorKeyParseError;
 
@@ -288,7 +338,17 @@ impl FromStr for DescriptorPublicKey {
 
         let (key_part, origin) = DescriptorXKey::<bip32::ExtendedPubKey>::parse_xkey_origin(s)?;
 
-        if key_part.contains("pub") {
+        if key_part.contains("pub") && key_part.contains('<') {
+            let (xpub, derivation_paths, wildcard) =
+                DescriptorXKey::<bip32::ExtendedPubKey>::parse_xkey_multi_deriv(key_part)?;
+
+            Ok(DescriptorPublicKey::MultiXPub(DescriptorMultiXKey {
+                origin,
+                xkey: xpub,
+                derivation_paths,
+                wildcard,
+            }))
+        } else if key_part.contains("pub") {
             let (xpub, derivation_path, wildcard) =
                 DescriptorXKey::<bip32::ExtendedPubKey>::parse_xkey_deriv(key_part)?;
 
@@ -325,6 +385,8 @@ pub enum ConversionError {
     HardenedChild,
     /// Attempted to convert a key with a hardened wildcard to a bitcoin public key
     HardenedWildcard,
+    /// Attempted to convert a key with several derivation paths to a bitcoin public key
+    MultiKey,
 }
 
 impl fmt::Display for ConversionError {
@@ -335,6 +397,7 @@ impl fmt::Display for ConversionError {
             ConversionError::HardenedWildcard => {
                 "hardened and uninstantiated wildcard in bip32 path"
             }
+            ConversionError::MultiKey => "multiple derivation paths in bip32 path",
         })
     }
 }
@@ -352,6 +415,13 @@ impl DescriptorPublicKey {
                     xpub.xkey.fingerprint()
                 }
             }
+            DescriptorPublicKey::MultiXPub(ref xpub) => {
+                if let Some((fingerprint, _)) = xpub.origin {
+                    fingerprint
+                } else {
+                    xpub.xkey.fingerprint()
+                }
+            }
             DescriptorPublicKey::SinglePub(ref single) => {
                 if let Some((fingerprint, _)) = single.origin {
                     fingerprint
@@ -372,6 +442,8 @@ impl DescriptorPublicKey {
     /// For wildcard keys this will return the path up to the wildcard, so you
     /// can get full paths by appending one additional derivation step, according
     /// to the wildcard type (hardened or normal)
+    ///
+    /// For multipath keys this returns the path of the first branch.
     pub fn full_derivation_path(&self) -> bip32::DerivationPath {
         match *self {
             DescriptorPublicKey::XPub(ref xpub) => {
@@ -382,6 +454,14 @@ impl DescriptorPublicKey {
                 };
                 origin_path.extend(&xpub.derivation_path)
             }
+            DescriptorPublicKey::MultiXPub(ref xpub) => {
+                let origin_path = if let Some((_, ref path)) = xpub.origin {
+                    path.clone()
+                } else {
+                    bip32::DerivationPath::from(vec![])
+                };
+                origin_path.extend(&xpub.derivation_paths[0])
+            }
             DescriptorPublicKey::SinglePub(ref single) => {
                 if let Some((_, ref path)) = single.origin {
                     path.clone()
@@ -397,6 +477,39 @@ impl DescriptorPublicKey {
         match *self {
             DescriptorPublicKey::SinglePub(..) => false,
             DescriptorPublicKey::XPub(ref xpub) => xpub.wildcard != Wildcard::None,
+            DescriptorPublicKey::MultiXPub(ref xpub) => xpub.wildcard != Wildcard::None,
+        }
+    }
+
+    /// Whether or not the key has several derivation paths
+    pub fn is_multipath(&self) -> bool {
+        match *self {
+            DescriptorPublicKey::MultiXPub(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Splits a multipath key into one key per derivation path, in the
+    /// order they were written. Other keys are returned unchanged.
+    pub fn into_single_keys(self) -> Vec<DescriptorPublicKey> {
+        match self {
+            DescriptorPublicKey::MultiXPub(xpub) => {
+                let origin = xpub.origin;
+                let xkey = xpub.xkey;
+                let wildcard = xpub.wildcard;
+                xpub.derivation_paths
+                    .into_iter()
+                    .map(|derivation_path| {
+                        DescriptorPublicKey::XPub(DescriptorXKey {
+                            origin: origin.clone(),
+                            xkey,
+                            derivation_path,
+                            wildcard,
+                        })
+                    })
+                    .collect()
+            }
+            key => vec![key],
         }
     }
 
@@ -420,6 +533,21 @@ impl DescriptorPublicKey {
             }
             xpub.wildcard = Wildcard::None;
             self = DescriptorPublicKey::XPub(xpub);
+        } else if let DescriptorPublicKey::MultiXPub(mut xpub) = self {
+            let child = match xpub.wildcard {
+                Wildcard::None => None,
+                Wildcard::Unhardened => Some(bip32::ChildNumber::from_normal_idx(index).unwrap()),
+                Wildcard::Hardened => Some(bip32::ChildNumber::from_hardened_idx(index).unwrap()),
+            };
+            if let Some(child) = child {
+                xpub.derivation_paths = xpub
+                    .derivation_paths
+                    .into_iter()
+                    .map(|path| path.into_child(child))
+                    .collect();
+            }
+            xpub.wildcard = Wildcard::None;
+            self = DescriptorPublicKey::MultiXPub(xpub);
         }
         self
     }
@@ -450,6 +578,7 @@ impl DescriptorPublicKey {
                     Err(e) => unreachable!("cryptographically unreachable: {}", e),
                 },
             },
+            DescriptorPublicKey::MultiXPub(..) => Err(ConversionError::MultiKey),
         }
     }
 }
@@ -574,6 +703,53 @@ impl<K: InnerXKey> DescriptorXKey<K> {
         Ok((xkey, derivation_path, wildcard))
     }
 
+    /// Parse an extended key concatenated to a derivation path containing
+    /// a single BIP389 `<a;b;...>` step, returning one path per alternative.
+    fn parse_xkey_multi_deriv(
+        key_deriv: &str,
+    ) -> Result<(K, Vec<bip32::DerivationPath>, Wildcard), DescriptorKeyParseError> {
+        let open = key_deriv
+            .find('<')
+            .ok_or(DescriptorKeyParseError("No '<' in multipath derivation"))?;
+        let close = key_deriv.find('>').ok_or(DescriptorKeyParseError(
+            "Unclosed '<' in multipath derivation",
+        ))?;
+        if close < open {
+            return Err(DescriptorKeyParseError(
+                "Unopened '>' in multipath derivation",
+            ));
+        }
+        let (prefix, suffix) = (&key_deriv[..open], &key_deriv[close + 1..]);
+        if !prefix.ends_with('/') || suffix.contains('<') || suffix.contains('>') {
+            return Err(DescriptorKeyParseError(
+                "Only one '<a;b>' step is allowed in a derivation path",
+            ));
+        }
+
+        let alternatives: Vec<&str> = key_deriv[open + 1..close].split(';').collect();
+        if alternatives.len() < 2 {
+            return Err(DescriptorKeyParseError(
+                "Multipath step must have at least two alternatives",
+            ));
+        }
+
+        let mut derivation_paths = Vec::with_capacity(alternatives.len());
+        let mut xkey_wildcard = None;
+        for alt in alternatives {
+            let (xkey, path, wildcard) =
+                Self::parse_xkey_deriv(&format!("{}{}{}", prefix, alt, suffix))?;
+            if derivation_paths.contains(&path) {
+                return Err(DescriptorKeyParseError(
+                    "Multipath step alternatives must be distinct",
+                ));
+            }
+            derivation_paths.push(path);
+            xkey_wildcard = Some((xkey, wildcard));
+        }
+        let (xkey, wildcard) = xkey_wildcard.expect("at least two alternatives");
+        Ok((xkey, derivation_paths, wildcard))
+    }
+
     /// Compares this key with a `keysource` and returns the matching derivation path, if any.
     ///
     /// For keys that have an origin, the `keysource`'s fingerprint will be compared
@@ -648,6 +824,108 @@ impl<K: InnerXKey> DescriptorXKey<K> {
     }
 }
 
+/// A [`DescriptorPublicKey`] which is statically known to have no
+/// wildcards and no hardened derivation steps, and so corresponds to
+/// exactly one public key.
+///
+/// Descriptors over this key type implement [`DescriptorTrait`](super::DescriptorTrait)
+/// methods such as `address`, which makes deriving a wildcard descriptor
+/// an explicit step (see [`Descriptor::at_derivation_index`](super::Descriptor::at_derivation_index)).
+#[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd, Hash)]
+pub struct DefiniteDescriptorKey(DescriptorPublicKey);
+
+impl DefiniteDescriptorKey {
+    /// Create a definite key from a [`DescriptorPublicKey`]. Errors if the
+    /// key has a wildcard or any hardened derivation steps.
+    pub fn new(key: DescriptorPublicKey) -> Result<Self, ConversionError> {
+        if let DescriptorPublicKey::XPub(ref xpk) = key {
+            match xpk.wildcard {
+                Wildcard::None => {}
+                Wildcard::Unhardened => return Err(ConversionError::Wildcard),
+                Wildcard::Hardened => return Err(ConversionError::HardenedWildcard),
+            }
+            for child in xpk.derivation_path.as_ref() {
+                if let bip32::ChildNumber::Hardened { .. } = *child {
+                    return Err(ConversionError::HardenedChild);
+                }
+            }
+        }
+        if key.is_multipath() {
+            return Err(ConversionError::MultiKey);
+        }
+        Ok(DefiniteDescriptorKey(key))
+    }
+
+    /// Computes the public key corresponding to this descriptor key.
+    /// Unlike [`DescriptorPublicKey::derive_public_key`] this cannot fail.
+    pub fn derive_public_key<C: secp256k1_zkp::Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+    ) -> bitcoin::PublicKey {
+        self.0
+            .derive_public_key(secp)
+            .expect("definite keys have no wildcards or hardened steps")
+    }
+
+    /// Borrow the underlying [`DescriptorPublicKey`]
+    pub fn as_descriptor_public_key(&self) -> &DescriptorPublicKey {
+        &self.0
+    }
+
+    /// Unwrap the underlying [`DescriptorPublicKey`]
+    pub fn into_descriptor_public_key(self) -> DescriptorPublicKey {
+        self.0
+    }
+}
+
+impl fmt::Display for DefiniteDescriptorKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for DefiniteDescriptorKey {
+    type Err = DescriptorKeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = DescriptorPublicKey::from_str(s)?;
+        DefiniteDescriptorKey::new(key).map_err(|_| {
+            DescriptorKeyParseError(
+                "Definite keys may not contain wildcards or hardened derivation steps",
+            )
+        })
+    }
+}
+
+impl From<DefiniteDescriptorKey> for DescriptorPublicKey {
+    fn from(key: DefiniteDescriptorKey) -> DescriptorPublicKey {
+        key.0
+    }
+}
+
+impl MiniscriptKey for DefiniteDescriptorKey {
+    type Hash = Self;
+
+    fn is_uncompressed(&self) -> bool {
+        self.0.is_uncompressed()
+    }
+
+    fn to_pubkeyhash(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl ToPublicKey for DefiniteDescriptorKey {
+    fn to_public_key(&self) -> bitcoin::PublicKey {
+        let secp = Secp256k1::verification_only();
+        self.derive_public_key(&secp)
+    }
+
+    fn hash_to_hash160(hash: &Self) -> hash160::Hash {
+        hash.to_public_key().to_pubkeyhash()
+    }
+}
+
 impl MiniscriptKey for DescriptorPublicKey {
     // This allows us to be able to derive public keys even for PkH s
     type Hash = Self;
@@ -668,7 +946,10 @@ impl MiniscriptKey for DescriptorPublicKey {
 
 #[cfg(test)]
 mod test {
-    use super::{DescriptorKeyParseError, DescriptorPublicKey, DescriptorSecretKey};
+    use super::{
+        ConversionError, DefiniteDescriptorKey, DescriptorKeyParseError, DescriptorPublicKey,
+        DescriptorSecretKey,
+    };
 
     use elements::secp256k1_zkp;
 
@@ -822,4 +1103,28 @@ mod test {
             "m/90'/0'/1'/2"
         );
     }
+
+    #[test]
+    fn definite_keys() {
+        let wildcard = DescriptorPublicKey::from_str("tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*").unwrap();
+        assert_eq!(
+            DefiniteDescriptorKey::new(wildcard.clone()),
+            Err(ConversionError::Wildcard)
+        );
+        assert!(DefiniteDescriptorKey::from_str(&wildcard.to_string()).is_err());
+
+        let derived = DefiniteDescriptorKey::new(wildcard.clone().derive(7)).unwrap();
+        assert_eq!(derived.to_string(), "tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/7");
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        assert_eq!(
+            derived.derive_public_key(&secp),
+            wildcard.derive(7).derive_public_key(&secp).unwrap()
+        );
+
+        let hardened = DescriptorPublicKey::from_str("tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0'/1").unwrap();
+        assert_eq!(
+            DefiniteDescriptorKey::new(hardened),
+            Err(ConversionError::HardenedChild)
+        );
+    }
 }
@@ -23,30 +23,42 @@
 //! these with BIP32 paths, pay-to-contract instructions, etc.
 //!
 
-use std::{collections::HashMap, sync::Arc};
+use std::{cmp, collections::HashMap, sync::Arc};
 use std::{
-    fmt,
+    fmt, ops,
     str::{self, FromStr},
 };
 
 #[allow(unused_imports)]
 pub mod pegin;
 
-// use bitcoin;
+use bitcoin;
+use bitcoin::util::bip32;
 use elements;
+use elements::hashes::{hash160, ripemd160, sha256, sha256d, Hash};
 use elements::secp256k1_zkp;
-use elements::Script;
+use elements::{script, Script};
 
+use extensions::{OutputConstraint, OutputsPref};
 use AllExt;
+use BtcDescriptor;
 use NoExt;
 
-use self::checksum::verify_checksum;
+use self::checksum::{strip_checksum, verify_checksum};
 use expression;
+use interpreter::{self, Interpreter};
 use miniscript;
+use miniscript::limits::{
+    MAX_SCRIPT_ELEMENT_SIZE, MAX_SCRIPT_SIZE, MAX_STACK_SIZE, MAX_STANDARD_P2WSH_SCRIPT_SIZE,
+    MAX_STANDARD_P2WSH_STACK_ITEMS,
+};
 use miniscript::{Legacy, Miniscript, Segwitv0};
+use policy::semantic::SpendPath;
+use policy::{LiftError, Liftable, Semantic};
+use util::{varint_len, witness_to_scriptsig};
 use {
-    BareCtx, Error, ForEach, ForEachKey, MiniscriptKey, Satisfier, ToPublicKey, TranslatePk,
-    TranslatePk2,
+    BareCtx, ElementsSig, Error, Extension, ForEach, ForEachKey, MiniscriptKey, Satisfier,
+    ScriptContext, ToPublicKey, TranslatePk, TranslatePk2, MAX_RECURSION_DEPTH,
 };
 
 mod bare;
@@ -63,10 +75,12 @@ pub use self::sh::{Sh, ShInner};
 pub use self::sortedmulti::SortedMultiVec;
 mod checksum;
 mod key;
+pub use self::checksum::verify_descriptor_checksum;
 pub use self::covenants::{CovError, CovOperations, CovSatisfier, CovenantDescriptor};
 pub use self::key::{
-    DescriptorKeyParseError, DescriptorPublicKey, DescriptorSecretKey, DescriptorSinglePriv,
-    DescriptorSinglePub, DescriptorXKey, InnerXKey, Wildcard,
+    ConversionError, DefiniteDescriptorKey, DescriptorKeyParseError, DescriptorMultiXKey,
+    DescriptorPublicKey, DescriptorSecretKey, DescriptorSinglePriv, DescriptorSinglePub,
+    DescriptorXKey, InnerXKey, Wildcard,
 };
 
 /// Alias type for a map of public key to secret key
@@ -77,8 +91,130 @@ pub use self::key::{
 /// public key from the descriptor.
 pub type KeyMap = HashMap<DescriptorPublicKey, DescriptorSecretKey>;
 
+/// A [`Satisfier`] signing a sighash message with the secret keys of a
+/// [`KeyMap`], e.g. the WIF keys or xprvs of a descriptor parsed with
+/// [`Descriptor::parse_descriptor`]
+///
+/// It satisfies the descriptor obtained from the parsed descriptor with
+/// [`Descriptor::at_derivation_index`] at the index it was created for.
+/// Keys are matched on the public key they derive to.
+pub struct KeyMapSatisfier<'a, C: secp256k1_zkp::Signing + 'a> {
+    secp: &'a secp256k1_zkp::Secp256k1<C>,
+    keys: HashMap<bitcoin::PublicKey, bitcoin::PrivateKey>,
+    msg: secp256k1_zkp::Message,
+    sighash_type: elements::SigHashType,
+}
+
+impl<'a, C: secp256k1_zkp::Signing> KeyMapSatisfier<'a, C> {
+    /// Create a satisfier producing signatures of `msg`, the sighash
+    /// computed with `sighash_type`, for the descriptor at derivation
+    /// `index`. The secret keys of `key_map` are derived once, here.
+    pub fn new(
+        secp: &'a secp256k1_zkp::Secp256k1<C>,
+        key_map: &KeyMap,
+        index: u32,
+        msg: secp256k1_zkp::Message,
+        sighash_type: elements::SigHashType,
+    ) -> Self {
+        let keys = key_map
+            .values()
+            .filter_map(|sk| {
+                let sk = match *sk {
+                    DescriptorSecretKey::SinglePriv(ref sk) => sk.key,
+                    DescriptorSecretKey::XPrv(ref xprv) => {
+                        let mut path: Vec<_> =
+                            (&xprv.derivation_path).into_iter().cloned().collect();
+                        match xprv.wildcard {
+                            Wildcard::None => {}
+                            Wildcard::Unhardened => {
+                                path.push(bip32::ChildNumber::from_normal_idx(index).ok()?)
+                            }
+                            Wildcard::Hardened => {
+                                path.push(bip32::ChildNumber::from_hardened_idx(index).ok()?)
+                            }
+                        }
+                        xprv.xkey.derive_priv(secp, &path).ok()?.private_key
+                    }
+                };
+                Some((sk.public_key(secp), sk))
+            })
+            .collect();
+        KeyMapSatisfier {
+            secp: secp,
+            keys: keys,
+            msg: msg,
+            sighash_type: sighash_type,
+        }
+    }
+
+    fn secret_key(&self, key: &DefiniteDescriptorKey) -> Option<bitcoin::PrivateKey> {
+        self.keys.get(&key.to_public_key()).cloned()
+    }
+}
+
+impl<'a, C: secp256k1_zkp::Signing> Satisfier<DefiniteDescriptorKey> for KeyMapSatisfier<'a, C> {
+    fn lookup_sig(&self, key: &DefiniteDescriptorKey) -> Option<ElementsSig> {
+        self.secret_key(key)
+            .map(|sk| (self.secp.sign(&self.msg, &sk.key), self.sighash_type))
+    }
+
+    fn lookup_pkh_pk(&self, hash: &DefiniteDescriptorKey) -> Option<DefiniteDescriptorKey> {
+        self.secret_key(hash).map(|_| hash.clone())
+    }
+
+    fn lookup_pkh_sig(
+        &self,
+        hash: &DefiniteDescriptorKey,
+    ) -> Option<(bitcoin::PublicKey, ElementsSig)> {
+        self.lookup_sig(hash).map(|sig| (hash.to_public_key(), sig))
+    }
+}
+
 /// Elements Descriptor String Prefix
 pub const ELMTS_STR: &str = "el";
+
+/// Names of descriptor wrappers, which are never miniscript fragments.
+/// The only valid nestings of these are
+///
+/// | outer                 | inner                                                  |
+/// |-----------------------|--------------------------------------------------------|
+/// | top level             | `elsh`, `elwsh`, `elwpkh`, `elpkh`, `elcovwsh`, bare miniscript |
+/// | `elsh`                | `wsh`, `wpkh`, `sortedmulti`, miniscript               |
+/// | `elwsh`, `elsh(wsh)`  | `sortedmulti`, miniscript                              |
+/// | `elcovwsh`            | miniscript                                             |
+/// | `blinded`             | any top level descriptor but `blinded`                 |
+/// | `pegin`               | a bitcoin descriptor and a top level descriptor        |
+///
+/// In particular taproot outputs can never appear inside a script hash.
+const DESCRIPTOR_WRAPPERS: [&str; 14] = [
+    "sh",
+    "wsh",
+    "wpkh",
+    "tr",
+    "covwsh",
+    "elsh",
+    "elwsh",
+    "elwpkh",
+    "elpkh",
+    "eltr",
+    "elcovwsh",
+    "blinded",
+    "pegin",
+    "legacy_pegin",
+];
+
+/// Checks that `inner` may appear as the argument of the descriptor
+/// `outer`, given the wrappers `allowed` at that position
+pub(crate) fn check_nesting(outer: &str, inner: &str, allowed: &[&str]) -> Result<(), Error> {
+    if DESCRIPTOR_WRAPPERS.contains(&inner) && !allowed.contains(&inner) {
+        Err(Error::InvalidNesting {
+            outer: outer.to_owned(),
+            inner: inner.to_owned(),
+        })
+    } else {
+        Ok(())
+    }
+}
 /// Elements specific additional features that
 /// we want on DescriptorTrait from upstream.
 // Maintained as a separate trait to avoid conflicts.
@@ -211,6 +347,34 @@ pub enum DescriptorType {
     Cov,
 }
 
+/// The signature hash algorithm used to sign for a descriptor
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum SighashContext {
+    /// Pre-segwit sighash, committing to the scriptCode but not the amount
+    Legacy,
+    /// BIP143-style segwit v0 sighash, committing to the spent amount
+    SegwitV0,
+}
+
+/// A potential problem with a descriptor which does not make it invalid
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Warning {
+    /// The key is shared by all the single-path descriptors a multipath
+    /// descriptor expands to, so e.g. receive and change scripts can be
+    /// linked to each other through it
+    MultipathKeyReuse(DescriptorPublicKey),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::MultipathKeyReuse(ref pk) => {
+                write!(f, "key {} is reused across multipath derivations", pk)
+            }
+        }
+    }
+}
+
 impl fmt::Display for DescriptorType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -334,6 +498,49 @@ impl DescriptorInfo {
     }
 }
 
+/// A hash commitment whose preimage is required by some spend path
+/// of a descriptor, as returned by [`Descriptor::hash_locks`]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum HashLock {
+    /// A SHA256 preimage is required
+    Sha256(sha256::Hash),
+    /// A SHA256d preimage is required
+    Hash256(sha256d::Hash),
+    /// A RIPEMD160 preimage is required
+    Ripemd160(ripemd160::Hash),
+    /// A HASH160 preimage is required
+    Hash160(hash160::Hash),
+}
+
+/// The differences between a descriptor and an upgraded version of it,
+/// as returned by [`Descriptor::diff`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DescriptorDiff<Pk: MiniscriptKey> {
+    /// Keys which only appear in the upgraded descriptor
+    pub added_keys: Vec<Pk>,
+    /// Keys which only appear in the original descriptor
+    pub removed_keys: Vec<Pk>,
+    /// The `(old, new)` values of the thresholds (`multi`, `sortedmulti`
+    /// and `thresh`) which changed
+    pub changed_thresholds: Vec<(usize, usize)>,
+    /// The `(old, new)` values of the `after` timelocks which changed
+    pub changed_after: Vec<(u32, u32)>,
+    /// The `(old, new)` values of the `older` timelocks which changed
+    pub changed_older: Vec<(u32, u32)>,
+}
+
+impl<Pk: MiniscriptKey> DescriptorDiff<Pk> {
+    /// Whether the two descriptors are equivalent up to the order of
+    /// their keys
+    pub fn is_empty(&self) -> bool {
+        self.added_keys.is_empty()
+            && self.removed_keys.is_empty()
+            && self.changed_thresholds.is_empty()
+            && self.changed_after.is_empty()
+            && self.changed_older.is_empty()
+    }
+}
+
 /// Script descriptor
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Descriptor<Pk: MiniscriptKey> {
@@ -443,6 +650,31 @@ impl<Pk: MiniscriptKey> Descriptor<Pk> {
         Ok(Descriptor::Cov(cov))
     }
 
+    /// Create a covenant descriptor which can only be spent by a transaction
+    /// whose outputs start with exactly `outputs`, in order, similar to an
+    /// `OP_CHECKTEMPLATEVERIFY` commitment
+    ///
+    /// This is `elcovwsh(pk,outputs_pref(<outputs>))`. Further outputs, such
+    /// as the fee output, may follow the committed ones unless they are
+    /// included in `outputs`. Errors if the serialized outputs exceed the
+    /// size limit of `outputs_pref`.
+    pub fn new_cov_outputs(pk: Pk, outputs: &[elements::TxOut]) -> Result<Self, Error> {
+        let mut pref = vec![];
+        for out in outputs {
+            pref.extend(elements::encode::serialize(out));
+        }
+        if pref.len() > MAX_SCRIPT_ELEMENT_SIZE {
+            return Err(Error::ContextError(
+                miniscript::context::ScriptContextError::CovElementSizeExceeded,
+            ));
+        }
+        let ms = Miniscript::from_ast(miniscript::decode::Terminal::Ext(AllExt::OutputsPref(
+            OutputsPref { pref: pref },
+        )))?;
+        Segwitv0::check_global_validity(&ms)?;
+        Descriptor::new_cov_wsh(pk, ms)
+    }
+
     /// Get the [DescriptorType] of [Descriptor]
     pub fn desc_type(&self) -> DescriptorType {
         match *self {
@@ -466,6 +698,24 @@ impl<Pk: MiniscriptKey> Descriptor<Pk> {
         }
     }
 
+    /// The sighash algorithm signatures for this descriptor must commit to
+    ///
+    /// Signing or verifying with the wrong algorithm gives signatures which
+    /// are invalid on chain, so callers should select the algorithm with
+    /// this method rather than from the descriptor string.
+    pub fn sighash_context(&self) -> SighashContext {
+        match *self {
+            Descriptor::Bare(..) | Descriptor::Pkh(..) => SighashContext::Legacy,
+            Descriptor::Wpkh(..) | Descriptor::Wsh(..) | Descriptor::Cov(..) => {
+                SighashContext::SegwitV0
+            }
+            Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                ShInner::Wsh(..) | ShInner::Wpkh(..) => SighashContext::SegwitV0,
+                ShInner::SortedMulti(..) | ShInner::Ms(..) => SighashContext::Legacy,
+            },
+        }
+    }
+
     /// Tries to convert descriptor as a covenant descriptor
     pub fn as_cov(&self) -> Result<&CovenantDescriptor<Pk, AllExt>, Error> {
         if let Descriptor::Cov(cov) = self {
@@ -475,10 +725,695 @@ impl<Pk: MiniscriptKey> Descriptor<Pk> {
         }
     }
 
+    /// Keys which appear more than once in the descriptor, e.g. in several
+    /// spend paths, in order of first reuse
+    ///
+    /// Reusing a key links its spend paths on chain and may let a single
+    /// signature authorize more than intended. This is a lint: unlike
+    /// [`DescriptorTrait::sanity_check`] it does not reject the descriptor.
+    /// Keys only present as hashes, as in `pk_h`, are not considered.
+    pub fn reused_keys(&self) -> Vec<Pk> {
+        let mut seen = vec![];
+        let mut reused = vec![];
+        self.for_each_key(|key| {
+            if let ForEach::Key(pk) = key {
+                if !seen.contains(pk) {
+                    seen.push(pk.clone());
+                } else if !reused.contains(pk) {
+                    reused.push(pk.clone());
+                }
+            }
+            true
+        });
+        reused
+    }
+
+    /// Conservatively checks that the descriptor is not obviously
+    /// unspendable, e.g. because a spend path combines height and time
+    /// based timelocks, or a `thresh` has fewer satisfiable subexpressions
+    /// than its threshold. Passing does not guarantee that a satisfaction
+    /// can be found.
+    ///
+    /// Covenant descriptors cannot be lifted, so only their timelocks are
+    /// checked.
+    pub fn check_satisfiable(&self) -> Result<(), Error> {
+        // `Miniscript::lift` also checks resource limits, which fail for
+        // any fragment without a satisfaction even when the descriptor is
+        // spendable through another branch, so lift the root term directly
+        fn check<Pk, Ctx, Ext>(ms: &Miniscript<Pk, Ctx, Ext>, lift: bool) -> Result<(), Error>
+        where
+            Pk: MiniscriptKey,
+            Ctx: ScriptContext,
+            Ext: Extension<Pk>,
+        {
+            if ms.has_mixed_timelocks() {
+                return Err(Error::LiftError(LiftError::HeightTimeLockCombination));
+            }
+            if lift && ms.as_inner().lift()?.normalized() == Semantic::Unsatisfiable {
+                return Err(Error::BadDescriptor(
+                    "descriptor can never be satisfied".to_owned(),
+                ));
+            }
+            Ok(())
+        }
+
+        match *self {
+            Descriptor::Bare(ref bare) => check(bare.as_inner(), true),
+            Descriptor::Pkh(..) | Descriptor::Wpkh(..) => Ok(()),
+            Descriptor::Wsh(ref wsh) => match *wsh.as_inner() {
+                WshInner::SortedMulti(..) => Ok(()),
+                WshInner::Ms(ref ms) => check(ms, true),
+            },
+            Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                ShInner::Wsh(ref wsh) => match *wsh.as_inner() {
+                    WshInner::SortedMulti(..) => Ok(()),
+                    WshInner::Ms(ref ms) => check(ms, true),
+                },
+                ShInner::Ms(ref ms) => check(ms, true),
+                ShInner::Wpkh(..) | ShInner::SortedMulti(..) => Ok(()),
+            },
+            Descriptor::Cov(ref cov) => check(cov.to_ms(), false),
+        }
+    }
+
     /// Return a string without the checksum
     pub fn to_string_no_chksum(&self) -> String {
         format!("{:?}", self)
     }
+
+    /// The explicit assets constrained by the covenant fragments of the
+    /// descriptor, without duplicates. Currently only `outputs_pref`
+    /// constrains assets. Empty for non-covenant descriptors.
+    pub fn referenced_assets(&self) -> Vec<elements::AssetId> {
+        let mut ret = vec![];
+        if let Descriptor::Cov(ref cov) = *self {
+            for ms in cov.to_ms().iter() {
+                if let miniscript::decode::Terminal::Ext(AllExt::OutputsPref(ref pref)) = ms.node {
+                    for asset in pref.referenced_assets() {
+                        if !ret.contains(&asset) {
+                            ret.push(asset);
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// The constraints the covenant fragments of the descriptor put on the
+    /// outputs of the spending transaction, without duplicates
+    ///
+    /// Currently only `outputs_pref` constrains outputs. Constraints from
+    /// all spend paths are listed together, in the order they occur in the
+    /// descriptor. Empty for non-covenant descriptors.
+    pub fn output_constraints(&self) -> Vec<OutputConstraint> {
+        let mut ret = vec![];
+        if let Descriptor::Cov(ref cov) = *self {
+            for ms in cov.to_ms().iter() {
+                if let miniscript::decode::Terminal::Ext(AllExt::OutputsPref(ref pref)) = ms.node {
+                    for constraint in pref.output_constraints() {
+                        if !ret.contains(&constraint) {
+                            ret.push(constraint);
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Lists the distinct hash locks appearing in any spend path of the
+    /// descriptor, in the order they occur in the descriptor string
+    pub fn hash_locks(&self) -> Vec<HashLock> {
+        fn collect<Pk, Ctx, Ext>(ms: &Miniscript<Pk, Ctx, Ext>, ret: &mut Vec<HashLock>)
+        where
+            Pk: MiniscriptKey,
+            Ctx: ScriptContext,
+            Ext: Extension<Pk>,
+        {
+            for node in ms.iter() {
+                let lock = match node.node {
+                    miniscript::decode::Terminal::Sha256(h) => HashLock::Sha256(h),
+                    miniscript::decode::Terminal::Hash256(h) => HashLock::Hash256(h),
+                    miniscript::decode::Terminal::Ripemd160(h) => HashLock::Ripemd160(h),
+                    miniscript::decode::Terminal::Hash160(h) => HashLock::Hash160(h),
+                    _ => continue,
+                };
+                if !ret.contains(&lock) {
+                    ret.push(lock);
+                }
+            }
+        }
+
+        let mut ret = vec![];
+        match *self {
+            Descriptor::Bare(ref bare) => collect(bare.as_inner(), &mut ret),
+            Descriptor::Pkh(..) | Descriptor::Wpkh(..) => {}
+            Descriptor::Wsh(ref wsh) => {
+                if let WshInner::Ms(ref ms) = *wsh.as_inner() {
+                    collect(ms, &mut ret);
+                }
+            }
+            Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                ShInner::Wsh(ref wsh) => {
+                    if let WshInner::Ms(ref ms) = *wsh.as_inner() {
+                        collect(ms, &mut ret);
+                    }
+                }
+                ShInner::Ms(ref ms) => collect(ms, &mut ret),
+                ShInner::Wpkh(..) | ShInner::SortedMulti(..) => {}
+            },
+            Descriptor::Cov(ref cov) => collect(cov.to_ms(), &mut ret),
+        }
+        ret
+    }
+
+    /// Compares the descriptor with an upgraded version `other` of it, e.g.
+    /// after adding a cosigner to a multisig
+    ///
+    /// Keys are compared as sets. Thresholds and timelocks are compared
+    /// pairwise in the order they occur in the descriptors, so both
+    /// descriptors must have the same type and the same number of each;
+    /// otherwise their shapes are incompatible and an error is returned.
+    /// Keys only referenced by their hash, as in `pk_h`, are not compared.
+    pub fn diff(&self, other: &Descriptor<Pk>) -> Result<DescriptorDiff<Pk>, Error> {
+        // Thresholds, `after` and `older` values in descriptor order
+        type Conditions = (Vec<usize>, Vec<u32>, Vec<u32>);
+
+        fn collect<Pk, Ctx, Ext>(ms: &Miniscript<Pk, Ctx, Ext>, ret: &mut Conditions)
+        where
+            Pk: MiniscriptKey,
+            Ctx: ScriptContext,
+            Ext: Extension<Pk>,
+        {
+            for node in ms.iter() {
+                match node.node {
+                    miniscript::decode::Terminal::Multi(k, _)
+                    | miniscript::decode::Terminal::Thresh(k, _) => ret.0.push(k),
+                    miniscript::decode::Terminal::After(n) => ret.1.push(n),
+                    miniscript::decode::Terminal::Older(n) => ret.2.push(n),
+                    _ => {}
+                }
+            }
+        }
+
+        fn conditions<Pk: MiniscriptKey>(desc: &Descriptor<Pk>) -> Conditions {
+            let mut ret = (vec![], vec![], vec![]);
+            match *desc {
+                Descriptor::Bare(ref bare) => collect(bare.as_inner(), &mut ret),
+                Descriptor::Pkh(..) | Descriptor::Wpkh(..) => {}
+                Descriptor::Wsh(ref wsh) => match *wsh.as_inner() {
+                    WshInner::SortedMulti(ref smv) => ret.0.push(smv.k),
+                    WshInner::Ms(ref ms) => collect(ms, &mut ret),
+                },
+                Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                    ShInner::Wsh(ref wsh) => match *wsh.as_inner() {
+                        WshInner::SortedMulti(ref smv) => ret.0.push(smv.k),
+                        WshInner::Ms(ref ms) => collect(ms, &mut ret),
+                    },
+                    ShInner::SortedMulti(ref smv) => ret.0.push(smv.k),
+                    ShInner::Ms(ref ms) => collect(ms, &mut ret),
+                    ShInner::Wpkh(..) => {}
+                },
+                Descriptor::Cov(ref cov) => collect(cov.to_ms(), &mut ret),
+            }
+            ret
+        }
+
+        fn keys<Pk: MiniscriptKey>(desc: &Descriptor<Pk>) -> Vec<Pk> {
+            let mut ret = vec![];
+            desc.for_each_key(|key| {
+                if let ForEach::Key(pk) = key {
+                    if !ret.contains(pk) {
+                        ret.push(pk.clone());
+                    }
+                }
+                true
+            });
+            ret
+        }
+
+        fn changed<T: Copy + PartialEq>(old: &[T], new: &[T]) -> Vec<(T, T)> {
+            old.iter()
+                .zip(new)
+                .filter(|&(a, b)| a != b)
+                .map(|(a, b)| (*a, *b))
+                .collect()
+        }
+
+        let (old, new) = (conditions(self), conditions(other));
+        if self.desc_type() != other.desc_type()
+            || old.0.len() != new.0.len()
+            || old.1.len() != new.1.len()
+            || old.2.len() != new.2.len()
+        {
+            return Err(Error::BadDescriptor(
+                "Cannot diff descriptors of different shapes".to_owned(),
+            ));
+        }
+        let (old_keys, new_keys) = (keys(self), keys(other));
+        Ok(DescriptorDiff {
+            added_keys: new_keys
+                .iter()
+                .filter(|pk| !old_keys.contains(pk))
+                .cloned()
+                .collect(),
+            removed_keys: old_keys
+                .iter()
+                .filter(|pk| !new_keys.contains(pk))
+                .cloned()
+                .collect(),
+            changed_thresholds: changed(&old.0, &new.0),
+            changed_after: changed(&old.1, &new.1),
+            changed_older: changed(&old.2, &new.2),
+        })
+    }
+
+    /// A stable identifier for the descriptor, useful to deduplicate
+    /// stored descriptors
+    ///
+    /// This is the SHA256 hash of the descriptor string without its checksum.
+    /// Wildcard keys are hashed as written, e.g. `xpub.../0/*`, so the id
+    /// identifies the whole range of derived descriptors rather than any
+    /// particular index.
+    pub fn id(&self) -> [u8; 32] {
+        sha256::Hash::hash(self.display_no_checksum().as_bytes()).into_inner()
+    }
+
+    // The `Display` form of the descriptor, without the trailing `#checksum`
+    fn display_no_checksum(&self) -> String {
+        let mut s = self.to_string();
+        if let Some(pos) = s.rfind('#') {
+            s.truncate(pos);
+        }
+        s
+    }
+}
+
+#[cfg(feature = "use-serde")]
+impl<Pk: MiniscriptKey> Descriptor<Pk> {
+    /// Describes the structure of the descriptor as a JSON object, e.g. for
+    /// rendering it in a GUI
+    ///
+    /// Descriptor nodes are objects with a `fragment` name as written in the
+    /// descriptor string (e.g. `elwsh`, or `wsh` inside `elsh`), their `keys`, the
+    /// threshold `k` of a `sortedmulti`, and the nested descriptor or
+    /// miniscript as `args`. Miniscripts are described by
+    /// [Miniscript::to_json_tree]. A bare descriptor is described by its
+    /// miniscript alone, as in the descriptor string.
+    pub fn to_json_tree(&self) -> ::serde_json::Value {
+        use serde_json::{Map, Value};
+
+        fn node(fragment: &str, keys: Vec<String>, args: Vec<Value>) -> Map<String, Value> {
+            let mut node = Map::new();
+            node.insert("fragment".to_owned(), Value::from(fragment));
+            if !keys.is_empty() {
+                node.insert("keys".to_owned(), Value::from(keys));
+            }
+            if !args.is_empty() {
+                node.insert("args".to_owned(), Value::from(args));
+            }
+            node
+        }
+        fn sortedmulti<Pk: MiniscriptKey, Ctx: miniscript::ScriptContext>(
+            smv: &SortedMultiVec<Pk, Ctx>,
+        ) -> Value {
+            let keys = smv.pks.iter().map(|pk| pk.to_string()).collect();
+            let mut sortedmulti = node("sortedmulti", keys, vec![]);
+            sortedmulti.insert("k".to_owned(), Value::from(smv.k));
+            Value::Object(sortedmulti)
+        }
+        fn wsh<Pk: MiniscriptKey>(fragment: &str, wsh: &Wsh<Pk>) -> Value {
+            let inner = match *wsh.as_inner() {
+                WshInner::SortedMulti(ref smv) => sortedmulti(smv),
+                WshInner::Ms(ref ms) => ms.to_json_tree(),
+            };
+            Value::Object(node(fragment, vec![], vec![inner]))
+        }
+        fn wpkh<Pk: MiniscriptKey>(fragment: &str, wpkh: &Wpkh<Pk>) -> Value {
+            Value::Object(node(fragment, vec![wpkh.as_inner().to_string()], vec![]))
+        }
+
+        match *self {
+            Descriptor::Bare(ref bare) => bare.as_inner().to_json_tree(),
+            Descriptor::Pkh(ref pkh) => {
+                Value::Object(node("elpkh", vec![pkh.as_inner().to_string()], vec![]))
+            }
+            Descriptor::Wpkh(ref w) => wpkh("elwpkh", w),
+            Descriptor::Wsh(ref w) => wsh("elwsh", w),
+            Descriptor::Sh(ref sh) => {
+                let inner = match *sh.as_inner() {
+                    ShInner::Wsh(ref w) => wsh("wsh", w),
+                    ShInner::Wpkh(ref w) => wpkh("wpkh", w),
+                    ShInner::SortedMulti(ref smv) => sortedmulti(smv),
+                    ShInner::Ms(ref ms) => ms.to_json_tree(),
+                };
+                Value::Object(node("elsh", vec![], vec![inner]))
+            }
+            Descriptor::Cov(ref cov) => Value::Object(node(
+                "elcovwsh",
+                vec![cov.pk().to_string()],
+                vec![cov.to_ms().to_json_tree()],
+            )),
+        }
+    }
+}
+
+/// Size in bytes of the rangeproof of a confidential output, as estimated
+/// by Elements Core for 52-bit values
+const RANGEPROOF_SIZE: usize = 4174;
+/// Size in bytes of the surjection proof of a confidential output, as
+/// estimated by Elements Core when committing to at most 3 inputs
+const SURJECTIONPROOF_SIZE: usize = 2 + 1 + 32 * 4;
+
+impl<Pk: MiniscriptKey + ToPublicKey> Descriptor<Pk>
+where
+    Pk: FromStr,
+    Pk::Hash: FromStr,
+    <Pk as FromStr>::Err: ToString,
+    <<Pk as MiniscriptKey>::Hash as FromStr>::Err: ToString,
+{
+    /// Computes the size in bytes of the encoded explicit script of the
+    /// descriptor (see [DescriptorTrait::explicit_script]). For `Wsh` and
+    /// `ShWsh` this is the witness script, for `Sh` the redeem script.
+    pub fn script_size(&self) -> usize {
+        self.explicit_script().len()
+    }
+
+    /// Checks that the explicit script of the descriptor does not exceed
+    /// the Elements size limit applicable to its descriptor type. The
+    /// redeemScript of a `Sh` descriptor is limited to a single script element,
+    /// witness scripts by standardness rules, and all other scripts by
+    /// consensus rules.
+    pub fn check_script_size(&self) -> Result<(), Error> {
+        let limit = match self.desc_type() {
+            DescriptorType::Sh | DescriptorType::ShSortedMulti => MAX_SCRIPT_ELEMENT_SIZE,
+            DescriptorType::Wsh
+            | DescriptorType::ShWsh
+            | DescriptorType::WshSortedMulti
+            | DescriptorType::ShWshSortedMulti
+            | DescriptorType::Cov => MAX_STANDARD_P2WSH_SCRIPT_SIZE,
+            _ => MAX_SCRIPT_SIZE,
+        };
+        if self.script_size() > limit {
+            Err(Error::ScriptSizeTooLarge)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Statically estimates an upper bound on the number of stack elements,
+    /// including the initial witness stack and the altstack, during the
+    /// execution of a satisfaction of this descriptor. See
+    /// [`Descriptor::check_exec_stack_items`] for the applicable limits.
+    /// Errs if the descriptor cannot be satisfied.
+    pub fn max_exec_stack_items(&self) -> Result<usize, Error> {
+        // Excludes the witness script, which is popped before execution
+        fn ms_items<Pk, Ctx, Ext>(ms: &Miniscript<Pk, Ctx, Ext>) -> Result<usize, Error>
+        where
+            Pk: MiniscriptKey + ToPublicKey,
+            Ctx: miniscript::ScriptContext,
+            Ext: Extension<Pk>,
+        {
+            Ok(ms.max_satisfaction_witness_elements()? - 1 + ms.max_exec_stack_extra())
+        }
+        // <k+1 witness elements> <k> <n keys> <n>
+        fn multi_items<Pk, Ctx>(smv: &SortedMultiVec<Pk, Ctx>) -> usize
+        where
+            Pk: MiniscriptKey,
+            Ctx: miniscript::ScriptContext,
+        {
+            smv.k + 1 + smv.pks.len() + 2
+        }
+        // <sig> <key> DUP HASH160 <hash>
+        let pkh_items = 4;
+
+        match *self {
+            Descriptor::Bare(ref bare) => ms_items(bare.as_inner()),
+            Descriptor::Pkh(..) | Descriptor::Wpkh(..) => Ok(pkh_items),
+            Descriptor::Wsh(ref wsh) => match *wsh.as_inner() {
+                WshInner::SortedMulti(ref smv) => Ok(multi_items(smv)),
+                WshInner::Ms(ref ms) => ms_items(ms),
+            },
+            Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                ShInner::Wsh(ref wsh) => match *wsh.as_inner() {
+                    WshInner::SortedMulti(ref smv) => Ok(multi_items(smv)),
+                    WshInner::Ms(ref ms) => ms_items(ms),
+                },
+                ShInner::Wpkh(..) => Ok(pkh_items),
+                ShInner::SortedMulti(ref smv) => Ok(multi_items(smv)),
+                ShInner::Ms(ref ms) => ms_items(ms),
+            },
+            Descriptor::Cov(ref cov) => {
+                // The 12 sighash message items are checked by the covenant
+                // script, which runs after the miniscript
+                let ms = cov.to_ms();
+                let cov_extra = script::Builder::new()
+                    .verify_cov(&cov.pk().to_public_key())
+                    .into_script()
+                    .instructions()
+                    .count();
+                Ok(ms.max_satisfaction_witness_elements()? - 1
+                    + 12
+                    + cmp::max(ms.max_exec_stack_extra(), 1 + cov_extra))
+            }
+        }
+    }
+
+    /// Checks that the estimate of [`Descriptor::max_exec_stack_items`] does
+    /// not exceed the Elements limit applicable to the descriptor type.
+    /// Segwit spends are limited to `MAX_STANDARD_P2WSH_STACK_ITEMS` by
+    /// standardness rules, and all other spends to `MAX_STACK_SIZE` by
+    /// consensus rules.
+    pub fn check_exec_stack_items(&self) -> Result<(), Error> {
+        let limit = match self.desc_type() {
+            DescriptorType::Wpkh
+            | DescriptorType::ShWpkh
+            | DescriptorType::Wsh
+            | DescriptorType::ShWsh
+            | DescriptorType::WshSortedMulti
+            | DescriptorType::ShWshSortedMulti
+            | DescriptorType::Cov => MAX_STANDARD_P2WSH_STACK_ITEMS,
+            _ => MAX_STACK_SIZE,
+        };
+        let items = self.max_exec_stack_items()?;
+        if items > limit {
+            Err(Error::StackSizeTooLarge(items))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Computes the weight an output paying to this descriptor adds to a
+    /// transaction, including its witness. Confidential outputs carry
+    /// commitments for the asset and value, an ECDH nonce and the
+    /// surjection and range proofs, whose sizes are estimated the same
+    /// way as in Elements Core.
+    pub fn output_weight(&self, confidential: bool) -> usize {
+        let spk_len = self.script_pubkey().len();
+        let (value_len, nonce_len, witness_size) = if confidential {
+            (
+                33,
+                33,
+                varint_len(SURJECTIONPROOF_SIZE)
+                    + SURJECTIONPROOF_SIZE
+                    + varint_len(RANGEPROOF_SIZE)
+                    + RANGEPROOF_SIZE,
+            )
+        } else {
+            // Explicit values and null nonces; the witness holds two
+            // empty proofs
+            (9, 1, 2)
+        };
+        4 * (33 + value_len + nonce_len + varint_len(spk_len) + spk_len) + witness_size
+    }
+
+    /// Computes the fee, in satoshis, attributable to spending one input of
+    /// this descriptor at a feerate of `feerate_sat_vb` satoshis per virtual
+    /// byte, for use in coin selection
+    ///
+    /// The input weight is the outpoint and sequence, the empty issuance
+    /// and peg-in witness fields, and [DescriptorTrait::max_satisfaction_weight].
+    /// As in Elements Core, the feerate is taken in satoshis per 1000 virtual
+    /// bytes and the fee is rounded up. Errors if the descriptor cannot be
+    /// satisfied.
+    pub fn input_fee(&self, feerate_sat_vb: f64) -> Result<u64, Error> {
+        // 32-byte txid, 4-byte vout and 4-byte sequence
+        let base_weight = 4 * (32 + 4 + 4);
+        // Empty issuance amount and inflation keys rangeproofs, and an empty
+        // peg-in witness stack
+        let elements_witness_weight = 3;
+        let weight = base_weight + elements_witness_weight + self.max_satisfaction_weight()?;
+        let vsize = ((weight + 3) / 4) as u64;
+        let sat_per_kvb = (feerate_sat_vb * 1000.0).round() as u64;
+        Ok((sat_per_kvb * vsize + 999) / 1000)
+    }
+
+    /// Recovers the signatures from a finalized spend of the descriptor,
+    /// e.g. to recombine them or to audit which keys authorized it
+    ///
+    /// Runs the interpreter on the spend's `script_sig` and `witness`, with
+    /// the transaction's `lock_time` and the input's `sequence` used to
+    /// check timelocks, and maps the key of every signature accepted by
+    /// `verify_sig` back to the descriptor's key. The signatures are
+    /// returned in the order their keys appear in the descriptor. As with
+    /// [`Interpreter::iter`], `verify_sig` should actually verify the
+    /// signatures, otherwise the signatures of a `multi` cannot be matched
+    /// to the right keys. Signatures for keys which only appear as a hash in
+    /// the descriptor, as in `pk_h(<hash>)`, are skipped.
+    ///
+    /// Errors if the spend does not satisfy the descriptor.
+    pub fn extract_signatures<F>(
+        &self,
+        script_sig: &Script,
+        witness: &[Vec<u8>],
+        lock_time: u32,
+        sequence: u32,
+        mut verify_sig: F,
+    ) -> Result<Vec<(Pk, ElementsSig)>, Error>
+    where
+        F: FnMut(&bitcoin::PublicKey, ElementsSig) -> bool,
+    {
+        let interpreter_err = |e: interpreter::Error| Error::Unexpected(e.to_string());
+        let spk = self.script_pubkey();
+        let mut interpreter =
+            Interpreter::from_txdata(&spk, script_sig, witness, lock_time, sequence)
+                .map_err(interpreter_err)?;
+        let mut sigs = vec![];
+        {
+            let record_sig = |pk: &bitcoin::PublicKey, sig: ElementsSig| {
+                let valid = verify_sig(pk, sig);
+                if valid {
+                    sigs.push((*pk, sig));
+                }
+                valid
+            };
+            for res in interpreter.iter(record_sig) {
+                res.map_err(interpreter_err)?;
+            }
+        }
+
+        let mut keys = vec![];
+        self.for_each_key(|key| {
+            if let ForEach::Key(pk) = key {
+                keys.push((pk.to_public_key(), pk.clone()));
+            }
+            true
+        });
+        // The interpreter visits `multi` signatures from the last key to the
+        // first, so match them up in the order of the descriptor's keys
+        let mut ret = vec![];
+        for (pk, key) in keys {
+            if let Some(pos) = sigs.iter().position(|&(ref sig_pk, _)| *sig_pk == pk) {
+                ret.push((key, sigs.remove(pos).1));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Populate the script fields of a PSET input spending this descriptor,
+    /// so that signers can produce signatures for it. Sets the
+    /// `redeem_script` for `Sh` descriptors and the `witness_script`
+    /// for `Wsh`, `ShWsh` and covenant descriptors; other fields are
+    /// left untouched.
+    pub fn update_pset_input(&self, input: &mut elements::pset::Input) {
+        match *self {
+            Descriptor::Bare(..) | Descriptor::Pkh(..) | Descriptor::Wpkh(..) => {}
+            Descriptor::Wsh(ref wsh) => input.witness_script = Some(wsh.explicit_script()),
+            Descriptor::Cov(ref cov) => input.witness_script = Some(cov.explicit_script()),
+            Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                ShInner::Wsh(ref wsh) => {
+                    input.redeem_script = Some(wsh.script_pubkey());
+                    input.witness_script = Some(wsh.explicit_script());
+                }
+                ShInner::Wpkh(ref wpkh) => input.redeem_script = Some(wpkh.script_pubkey()),
+                ShInner::SortedMulti(..) | ShInner::Ms(..) => {
+                    input.redeem_script = Some(sh.explicit_script())
+                }
+            },
+        }
+    }
+
+    /// Computes the canonical dissatisfying witness and scriptSig for the
+    /// descriptor, under which its top-level fragment evaluates to false.
+    /// This is useful to prove that a branch is not taken. Errs with
+    /// [`Error::CouldNotSatisfy`] for descriptors that cannot be
+    /// dissatisfied, such as covenants or those ending in a `v:` wrapper
+    /// or a timelock, and for `pk_h` fragments, whose dissatisfaction
+    /// requires the public key.
+    pub fn dissatisfy(&self) -> Result<(Vec<Vec<u8>>, Script), Error> {
+        fn wsh_witness<Pk: MiniscriptKey + ToPublicKey>(
+            wsh: &Wsh<Pk>,
+        ) -> Result<Vec<Vec<u8>>, Error>
+        where
+            Pk: FromStr,
+            Pk::Hash: FromStr,
+            <Pk as FromStr>::Err: ToString,
+            <<Pk as MiniscriptKey>::Hash as FromStr>::Err: ToString,
+        {
+            let mut witness = match *wsh.as_inner() {
+                WshInner::SortedMulti(ref smv) => vec![vec![]; smv.k + 1],
+                WshInner::Ms(ref ms) => ms.dissatisfy(())?,
+            };
+            witness.push(wsh.explicit_script().into_bytes());
+            Ok(witness)
+        }
+
+        match *self {
+            Descriptor::Bare(ref bare) => {
+                let stack = bare.as_inner().dissatisfy(())?;
+                Ok((vec![], witness_to_scriptsig(&stack)))
+            }
+            Descriptor::Pkh(ref pkh) => {
+                let pk = pkh.as_inner().to_public_key().to_bytes();
+                Ok((vec![], witness_to_scriptsig(&[vec![], pk])))
+            }
+            Descriptor::Wpkh(ref wpkh) => {
+                let pk = wpkh.as_inner().to_public_key().to_bytes();
+                Ok((vec![vec![], pk], Script::new()))
+            }
+            Descriptor::Wsh(ref wsh) => Ok((wsh_witness(wsh)?, Script::new())),
+            Descriptor::Sh(ref sh) => match *sh.as_inner() {
+                ShInner::Wsh(ref wsh) => Ok((wsh_witness(wsh)?, sh.unsigned_script_sig())),
+                ShInner::Wpkh(ref wpkh) => {
+                    let pk = wpkh.as_inner().to_public_key().to_bytes();
+                    Ok((vec![vec![], pk], sh.unsigned_script_sig()))
+                }
+                ShInner::SortedMulti(ref smv) => {
+                    let mut stack = vec![vec![]; smv.k + 1];
+                    stack.push(smv.encode().into_bytes());
+                    Ok((vec![], witness_to_scriptsig(&stack)))
+                }
+                ShInner::Ms(ref ms) => {
+                    let mut stack = ms.dissatisfy(())?;
+                    stack.push(ms.encode().into_bytes());
+                    Ok((vec![], witness_to_scriptsig(&stack)))
+                }
+            },
+            Descriptor::Cov(..) => Err(Error::CouldNotSatisfy),
+        }
+    }
+
+    /// Lists the spend paths of the descriptor for which every required
+    /// key is present in `keystore`. Paths may additionally require
+    /// timelocks or hash preimages, which are listed in the returned
+    /// [`SpendPath`]s but not checked. Errs if the descriptor cannot be
+    /// lifted to a semantic policy, e.g. for covenant descriptors.
+    pub fn signable_paths(
+        &self,
+        keystore: &HashMap<bitcoin::PublicKey, secp256k1_zkp::SecretKey>,
+    ) -> Result<Vec<SpendPath<Pk>>, Error> {
+        let available: Vec<_> = keystore.keys().map(|pk| pk.to_pubkeyhash()).collect();
+        let paths = self.lift()?.spend_paths();
+        Ok(paths
+            .into_iter()
+            .filter(|path| {
+                path.keys
+                    .iter()
+                    .all(|pkh| available.contains(&Pk::hash_to_hash160(pkh)))
+            })
+            .collect())
+    }
 }
 
 impl<P: MiniscriptKey, Q: MiniscriptKey> TranslatePk<P, Q> for Descriptor<P> {
@@ -712,6 +1647,51 @@ impl<Pk: MiniscriptKey> ForEachKey<Pk> for Descriptor<Pk> {
 }
 
 impl Descriptor<DescriptorPublicKey> {
+    /// Creates the sorted multisig descriptor of a wallet shared by several
+    /// cosigners, each contributing one (usually ranged) extended key
+    ///
+    /// `kind` selects the script type and must be one of
+    /// [`DescriptorType::WshSortedMulti`], [`DescriptorType::ShWshSortedMulti`]
+    /// or [`DescriptorType::ShSortedMulti`]. Errors on any other kind, or if
+    /// two cosigners provided keys derived from the same extended key.
+    pub fn new_multisig_wallet(
+        threshold: usize,
+        xpubs: Vec<DescriptorPublicKey>,
+        kind: DescriptorType,
+    ) -> Result<Self, Error> {
+        fn same_cosigner(a: &DescriptorPublicKey, b: &DescriptorPublicKey) -> bool {
+            let xkey = |pk: &DescriptorPublicKey| match *pk {
+                DescriptorPublicKey::SinglePub(..) => None,
+                DescriptorPublicKey::XPub(ref xpub) => Some(xpub.xkey),
+                DescriptorPublicKey::MultiXPub(ref xpub) => Some(xpub.xkey),
+            };
+            match (xkey(a), xkey(b)) {
+                (Some(a), Some(b)) => a == b,
+                _ => a == b,
+            }
+        }
+
+        for (i, xpub) in xpubs.iter().enumerate() {
+            if xpubs[..i].iter().any(|other| same_cosigner(xpub, other)) {
+                return Err(Error::BadDescriptor(format!(
+                    "Cosigner key {} appears more than once",
+                    xpub
+                )));
+            }
+        }
+        match kind {
+            DescriptorType::WshSortedMulti => Descriptor::new_wsh_sortedmulti(threshold, xpubs),
+            DescriptorType::ShWshSortedMulti => {
+                Descriptor::new_sh_wsh_sortedmulti(threshold, xpubs)
+            }
+            DescriptorType::ShSortedMulti => Descriptor::new_sh_sortedmulti(threshold, xpubs),
+            _ => Err(Error::BadDescriptor(format!(
+                "{} is not a sortedmulti descriptor type",
+                kind
+            ))),
+        }
+    }
+
     /// Whether or not the descriptor has any wildcards
     pub fn is_deriveable(&self) -> bool {
         self.for_any_key(|key| key.as_key().is_deriveable())
@@ -724,26 +1704,299 @@ impl Descriptor<DescriptorPublicKey> {
         self.translate_pk2_infallible(|pk| pk.clone().derive(index))
     }
 
-    /// Parse a descriptor that may contain secret keys
-    ///
-    /// Internally turns every secret key found into the corresponding public key and then returns a
-    /// a descriptor that only contains public keys and a map to lookup the secret key given a public key.
-    pub fn parse_descriptor<C: secp256k1_zkp::Signing>(
-        secp: &secp256k1_zkp::Secp256k1<C>,
-        s: &str,
-    ) -> Result<(Descriptor<DescriptorPublicKey>, KeyMap), Error> {
-        let parse_key = |s: &String,
-                         key_map: &mut KeyMap|
-         -> Result<DescriptorPublicKey, DescriptorKeyParseError> {
-            let (public_key, secret_key) = match DescriptorSecretKey::from_str(s) {
-                Ok(sk) => (sk.as_public(&secp)?, Some(sk)),
-                Err(_) => (DescriptorPublicKey::from_str(s)?, None),
-            };
+    /// Whether or not the descriptor has any multipath (`<0;1>`) keys
+    pub fn is_multipath(&self) -> bool {
+        self.for_any_key(|key| key.as_key().is_multipath())
+    }
 
-            if let Some(secret_key) = secret_key {
-                key_map.insert(public_key.clone(), secret_key);
+    /// Expands a multipath descriptor into one descriptor per derivation
+    /// path, e.g. the receive and change descriptors of `.../<0;1>/*`
+    ///
+    /// Descriptors without multipath keys are returned as a single element.
+    /// Errors if the multipath keys do not all have the same number of paths.
+    pub fn into_single_descriptors(self) -> Result<Vec<Descriptor<DescriptorPublicKey>>, Error> {
+        let mut n_paths = None;
+        let mismatch = self.for_any_key(|key| match *key.as_key() {
+            DescriptorPublicKey::MultiXPub(ref xpub) => {
+                let n = xpub.derivation_paths.len();
+                n != *n_paths.get_or_insert(n)
             }
-
+            _ => false,
+        });
+        if mismatch {
+            return Err(Error::BadDescriptor(
+                "Multipath keys with different numbers of paths".to_owned(),
+            ));
+        }
+        match n_paths {
+            None => Ok(vec![self]),
+            Some(n) => Ok((0..n)
+                .map(|i| {
+                    self.translate_pk2_infallible(|pk| {
+                        let mut keys = pk.clone().into_single_keys();
+                        if keys.len() == 1 {
+                            keys.remove(0)
+                        } else {
+                            keys.remove(i)
+                        }
+                    })
+                })
+                .collect()),
+        }
+    }
+
+    /// Lints a multipath descriptor for keys which are reused across the
+    /// descriptors it expands to
+    ///
+    /// Every key without a multipath step appears unchanged in each of the
+    /// expanded descriptors (see [`Descriptor::into_single_descriptors`]),
+    /// and is reported once. Descriptors without multipath keys have no
+    /// sibling paths and never produce warnings.
+    pub fn check_multipath_key_reuse(&self) -> Vec<Warning> {
+        let mut warnings = vec![];
+        if !self.is_multipath() {
+            return warnings;
+        }
+        self.for_each_key(|key| {
+            let key = key.as_key();
+            if !key.is_multipath() {
+                let warning = Warning::MultipathKeyReuse(key.clone());
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+            true
+        });
+        warnings
+    }
+
+    /// Derives all wildcard keys in the descriptor using the supplied index,
+    /// returning a descriptor which is statically known to have no wildcards
+    ///
+    /// Errors if any key has hardened derivation steps.
+    /// Panics if given an index ≥ 2^31
+    pub fn at_derivation_index(
+        &self,
+        index: u32,
+    ) -> Result<Descriptor<DefiniteDescriptorKey>, ConversionError> {
+        self.translate_pk2(|pk| DefiniteDescriptorKey::new(pk.clone().derive(index)))
+    }
+
+    /// Computes the scriptPubKey of the descriptor at the given index
+    ///
+    /// This gives the same result as calling [`Descriptor::derive`], converting
+    /// every key with [`DescriptorPublicKey::derive_public_key`] and computing
+    /// the scriptPubKey of the result, but translates the keys in a single pass
+    /// without building the intermediate descriptor. Useful for scanning large
+    /// ranges of a wallet's scriptPubKeys.
+    ///
+    /// Panics if given an index ≥ 2^31
+    pub fn derived_script_pubkey<C: secp256k1_zkp::Verification>(
+        &self,
+        secp: &secp256k1_zkp::Secp256k1<C>,
+        index: u32,
+    ) -> Result<Script, ConversionError> {
+        let derived = self.translate_pk2(|pk| pk.clone().derive(index).derive_public_key(secp))?;
+        Ok(derived.script_pubkey())
+    }
+
+    /// Computes the address of the descriptor at the given index, as
+    /// returned by Elements Core's `deriveaddresses` for the same
+    /// descriptor. Errors if any key cannot be derived or if the
+    /// descriptor has no address form, as for bare descriptors.
+    ///
+    /// Panics if given an index ≥ 2^31
+    pub fn derived_address<C: secp256k1_zkp::Verification>(
+        &self,
+        secp: &secp256k1_zkp::Secp256k1<C>,
+        index: u32,
+        params: &'static elements::AddressParams,
+    ) -> Result<elements::Address, Error> {
+        let derived = self
+            .translate_pk2(|pk| pk.clone().derive(index).derive_public_key(secp))
+            .map_err(|e| Error::BadDescriptor(e.to_string()))?;
+        derived.address(params)
+    }
+
+    /// Whether this descriptor and `other` produce the same scriptPubKeys
+    /// for every index in `range`
+    ///
+    /// Useful to check that a descriptor written in a different form (e.g.
+    /// with a different key order in a `sortedmulti`) still describes the
+    /// same wallet. Errors if any key cannot be derived.
+    pub fn same_spk_as<C: secp256k1_zkp::Verification>(
+        &self,
+        other: &Descriptor<DescriptorPublicKey>,
+        secp: &secp256k1_zkp::Secp256k1<C>,
+        range: ops::Range<u32>,
+    ) -> Result<bool, ConversionError> {
+        for index in range {
+            if self.derived_script_pubkey(secp, index)?
+                != other.derived_script_pubkey(secp, index)?
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Finds the index in `range` at which the descriptor derives `spk`,
+    /// e.g. to check ownership of UTXOs found during a rescan. Uses
+    /// [`Descriptor::derived_script_pubkey`], so no addresses are encoded.
+    /// Errors if any key cannot be derived.
+    pub fn find_derivation_index<C: secp256k1_zkp::Verification>(
+        &self,
+        secp: &secp256k1_zkp::Secp256k1<C>,
+        spk: &Script,
+        range: ops::Range<u32>,
+    ) -> Result<Option<u32>, ConversionError> {
+        for index in range {
+            if self.derived_script_pubkey(secp, index)? == *spk {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the first key in the descriptor whose master fingerprint is
+    /// `fingerprint`, returning its full derivation path from the master
+    /// key along with the key itself
+    ///
+    /// This is what a hardware wallet needs to determine whether, and with
+    /// which key, it can sign for the descriptor. For wildcard keys the
+    /// returned path stops before the wildcard.
+    pub fn find_key_by_fingerprint(
+        &self,
+        fingerprint: bip32::Fingerprint,
+    ) -> Option<(bip32::DerivationPath, &DescriptorPublicKey)> {
+        let mut found = None;
+        self.for_any_key(|key| {
+            let key = key.as_key();
+            if key.master_fingerprint() == fingerprint {
+                found = Some(key);
+                true
+            } else {
+                false
+            }
+        });
+        found.map(|key| (key.full_derivation_path(), key))
+    }
+
+    /// Returns the change descriptor matching this receive descriptor
+    ///
+    /// Every wildcard key must either end in the receive path `/0/*`, which
+    /// is replaced by `/1/*`, or be a multipath key `/<0;1>/*` (or another
+    /// multipath step whose two paths end in `0` and `1`), of which the
+    /// second path is taken. Keys without wildcards are kept as they are.
+    /// Returns `None` for descriptors without wildcards or with any other
+    /// derivation.
+    pub fn change_descriptor(&self) -> Option<Descriptor<DescriptorPublicKey>> {
+        let receive = bip32::ChildNumber::from_normal_idx(0).expect("0 is a valid index");
+        let change = bip32::ChildNumber::from_normal_idx(1).expect("1 is a valid index");
+
+        if !self.is_deriveable() {
+            return None;
+        }
+        self.translate_pk2(|pk| match *pk {
+            DescriptorPublicKey::XPub(ref xpub) if xpub.wildcard == Wildcard::None => {
+                Ok(pk.clone())
+            }
+            DescriptorPublicKey::XPub(ref xpub)
+                if xpub.wildcard == Wildcard::Unhardened
+                    && xpub.derivation_path.as_ref().last() == Some(&receive) =>
+            {
+                let mut path = xpub.derivation_path.as_ref().to_vec();
+                *path.last_mut().expect("non-empty path") = change;
+                let mut xpub = xpub.clone();
+                xpub.derivation_path = bip32::DerivationPath::from(path);
+                Ok(DescriptorPublicKey::XPub(xpub))
+            }
+            DescriptorPublicKey::MultiXPub(ref xpub)
+                if xpub.wildcard == Wildcard::Unhardened
+                    && xpub.derivation_paths.len() == 2
+                    && xpub.derivation_paths[0].as_ref().last() == Some(&receive)
+                    && xpub.derivation_paths[1].as_ref().last() == Some(&change) =>
+            {
+                Ok(pk.clone().into_single_keys().remove(1))
+            }
+            DescriptorPublicKey::SinglePub(..) => Ok(pk.clone()),
+            _ => Err(()),
+        })
+        .ok()
+    }
+
+    /// Splits the descriptor into a BIP388 wallet policy, as registered on
+    /// hardware signers: a template in which every key is replaced by `@i`
+    /// followed by its derivation steps, and the list of distinct keys the
+    /// `@i` refer to, in order of first appearance. The keys keep their
+    /// origin but have their derivation steps removed.
+    ///
+    /// Multipath `/<0;1>/*` steps are written as the `/**` shorthand. BIP388
+    /// signers only accept keys derived with `/**` or `/<M;N>/*`, which is
+    /// not checked here.
+    pub fn to_wallet_policy(&self) -> (String, Vec<DescriptorPublicKey>) {
+        fn key_info(pk: &DescriptorPublicKey) -> DescriptorPublicKey {
+            match *pk {
+                DescriptorPublicKey::SinglePub(..) => pk.clone(),
+                DescriptorPublicKey::XPub(ref xpub) => DescriptorPublicKey::XPub(DescriptorXKey {
+                    origin: xpub.origin.clone(),
+                    xkey: xpub.xkey,
+                    derivation_path: bip32::DerivationPath::from(vec![]),
+                    wildcard: Wildcard::None,
+                }),
+                DescriptorPublicKey::MultiXPub(ref xpub) => {
+                    DescriptorPublicKey::XPub(DescriptorXKey {
+                        origin: xpub.origin.clone(),
+                        xkey: xpub.xkey,
+                        derivation_path: bip32::DerivationPath::from(vec![]),
+                        wildcard: Wildcard::None,
+                    })
+                }
+            }
+        }
+
+        let mut key_infos = vec![];
+        self.for_each_key(|key| {
+            let info = key_info(key.as_key());
+            if !key_infos.contains(&info) {
+                key_infos.push(info);
+            }
+            true
+        });
+        let template = self.translate_pk2_infallible(|pk| {
+            let info = key_info(pk);
+            let index = key_infos
+                .iter()
+                .position(|k| *k == info)
+                .expect("all keys were collected");
+            let full = pk.to_string();
+            let suffix = full[info.to_string().len()..].replace("/<0;1>/*", "/**");
+            format!("@{}{}", index, suffix)
+        });
+        let template = strip_checksum(&template.to_string()).to_owned();
+        (template, key_infos)
+    }
+
+    /// Parse a descriptor that may contain secret keys
+    ///
+    /// Internally turns every secret key found into the corresponding public key and then returns a
+    /// a descriptor that only contains public keys and a map to lookup the secret key given a public key.
+    pub fn parse_descriptor<C: secp256k1_zkp::Signing>(
+        secp: &secp256k1_zkp::Secp256k1<C>,
+        s: &str,
+    ) -> Result<(Descriptor<DescriptorPublicKey>, KeyMap), Error> {
+        let parse_key = |s: &String,
+                         key_map: &mut KeyMap|
+         -> Result<DescriptorPublicKey, DescriptorKeyParseError> {
+            let (public_key, secret_key) = match DescriptorSecretKey::from_str(s) {
+                Ok(sk) => (sk.as_public(&secp)?, Some(sk)),
+                Err(_) => (DescriptorPublicKey::from_str(s)?, None),
+            };
+
+            if let Some(secret_key) = secret_key {
+                key_map.insert(public_key.clone(), secret_key);
+            }
+
             Ok(public_key)
         };
 
@@ -803,25 +2056,107 @@ where
     }
 }
 
-impl<Pk> FromStr for Descriptor<Pk>
+impl<Pk> Descriptor<Pk>
 where
     Pk: MiniscriptKey + str::FromStr,
     Pk::Hash: str::FromStr,
     <Pk as FromStr>::Err: ToString,
     <<Pk as MiniscriptKey>::Hash as FromStr>::Err: ToString,
 {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Descriptor<Pk>, Error> {
+    /// Parse a descriptor, failing with [`Error::MaxRecursiveDepthExceeded`]
+    /// if its expressions are nested more than `max_depth` levels deep.
+    /// [`FromStr`] uses a limit of 402; services parsing untrusted
+    /// descriptors can use this to enforce a tighter one.
+    pub fn from_str_with_max_depth(s: &str, max_depth: u32) -> Result<Descriptor<Pk>, Error> {
+        // Whitespace is not significant in descriptors, strip it so that
+        // descriptors can be split over several lines. The checksum is
+        // computed over the whitespace-free form.
+        let stripped: String;
+        let s = if s.contains(char::is_whitespace) {
+            stripped = s.chars().filter(|c| !c.is_whitespace()).collect();
+            &stripped[..]
+        } else {
+            s
+        };
         if !s.starts_with(ELMTS_STR) {
             return Err(Error::BadDescriptor(String::from(
                 "Not an Elements Descriptor",
             )));
         }
         let desc_str = verify_checksum(&s)?;
-        let top = expression::Tree::from_str(desc_str)?;
+        let top = expression::Tree::from_str_with_max_depth(desc_str, max_depth)?;
         expression::FromTree::from_tree(&top)
     }
+
+    /// Instantiates a descriptor template whose timelocks are named
+    /// parameters, e.g. `older(@delay)` or `after(@expiry)`, taking the
+    /// value of each `@name` from `params`. Errs if a parameter has no
+    /// value or a placeholder appears anywhere but as a timelock.
+    pub fn instantiate(
+        template: &str,
+        params: &HashMap<String, u32>,
+    ) -> Result<Descriptor<Pk>, Error> {
+        let template = verify_checksum(template)?;
+        let mut desc = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(pos) = rest.find('@') {
+            let (head, tail) = rest.split_at(pos);
+            if !head.ends_with("older(") && !head.ends_with("after(") {
+                return Err(Error::BadDescriptor(format!(
+                    "unexpected template parameter at {}",
+                    tail
+                )));
+            }
+            let end = tail.find(')').unwrap_or(tail.len());
+            let name = &tail[1..end];
+            match params.get(name) {
+                Some(value) => {
+                    desc.push_str(head);
+                    desc.push_str(&value.to_string());
+                }
+                None => {
+                    return Err(Error::BadDescriptor(format!(
+                        "no value for template parameter @{}",
+                        name
+                    )))
+                }
+            }
+            rest = &tail[end..];
+        }
+        desc.push_str(rest);
+        Descriptor::from_str(&desc)
+    }
+
+    /// Converts the descriptor to the equivalent bitcoin descriptor, e.g.
+    /// to monitor the bitcoin side of a peg-in with rust-bitcoin tooling.
+    /// Both descriptors produce the same scripts. Errs for covenant
+    /// descriptors, which rely on Elements-only opcodes.
+    pub fn to_bitcoin_descriptor(&self) -> Result<BtcDescriptor<Pk>, Error> {
+        if let Descriptor::Cov(..) = *self {
+            return Err(Error::BadDescriptor(String::from(
+                "covenant descriptors have no bitcoin equivalent",
+            )));
+        }
+        // Apart from the top-level prefix, all other descriptors use the
+        // same syntax as bitcoin descriptors; nested descriptors like the
+        // `wsh` of `elsh(wsh(..))` are displayed without the prefix
+        let desc_str = self.display_no_checksum();
+        BtcDescriptor::from_str(&desc_str[ELMTS_STR.len()..]).map_err(Error::BtcError)
+    }
+}
+
+impl<Pk> FromStr for Descriptor<Pk>
+where
+    Pk: MiniscriptKey + str::FromStr,
+    Pk::Hash: str::FromStr,
+    <Pk as FromStr>::Err: ToString,
+    <<Pk as MiniscriptKey>::Hash as FromStr>::Err: ToString,
+{
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Descriptor<Pk>, Error> {
+        Descriptor::from_str_with_max_depth(s, MAX_RECURSION_DEPTH)
+    }
 }
 
 impl<Pk: MiniscriptKey> fmt::Debug for Descriptor<Pk> {
@@ -858,12 +2193,13 @@ mod tests {
     use super::DescriptorTrait;
     use bitcoin;
     use bitcoin::hashes::hex::FromHex;
-    use bitcoin::hashes::{hash160, sha256};
+    use bitcoin::hashes::{hash160, sha256, Hash};
     use bitcoin::util::bip32;
     use bitcoin::PublicKey;
     use descriptor::key::Wildcard;
     use descriptor::{
-        DescriptorPublicKey, DescriptorSecretKey, DescriptorSinglePub, DescriptorXKey,
+        ConversionError, DescriptorPublicKey, DescriptorSecretKey, DescriptorSinglePub,
+        DescriptorType, DescriptorXKey, Sh, Warning, Wsh,
     };
 
     use elements::opcodes::{
@@ -874,11 +2210,17 @@ mod tests {
     use elements::{self, secp256k1_zkp};
     use elements::{script, Script};
     use hex_script;
+    use miniscript::decode::Terminal;
     use miniscript::satisfy::ElementsSig;
+    use policy::{LiftError, Liftable, Semantic};
     use std::cmp;
     use std::collections::HashMap;
     use std::str::FromStr;
-    use {Descriptor, DummyKey, Error, Miniscript, Satisfier, TranslatePk2};
+    use std::sync::Arc;
+    use {
+        Descriptor, DummyKey, Error, ForEach, ForEachKey, Miniscript, MiniscriptKey, Satisfier,
+        TranslatePk2,
+    };
 
     #[cfg(feature = "compiler")]
     use policy;
@@ -984,6 +2326,318 @@ mod tests {
         .unwrap_err();
     }
 
+    #[test]
+    fn script_size() {
+        let secp = bitcoin::secp256k1::Secp256k1::signing_only();
+        let pks: Vec<String> = (1..101u8)
+            .map(|i| {
+                let sk = bitcoin::secp256k1::SecretKey::from_slice(&[i; 32]).unwrap();
+                bitcoin::PublicKey {
+                    compressed: true,
+                    key: bitcoin::secp256k1::PublicKey::from_secret_key(&secp, &sk),
+                }
+                .to_string()
+            })
+            .collect();
+        // 20-key multi: OP_1 <20 x 34-byte pushes> <20> OP_CHECKMULTISIG,
+        // where 20 is above OP_16 and takes a 2-byte push
+        let multi = |chunk: &[String]| format!("multi(1,{})", chunk.join(","));
+        let pk_vec = |chunk: &[String]| -> Vec<PublicKey> {
+            chunk
+                .iter()
+                .map(|pk| PublicKey::from_str(pk).unwrap())
+                .collect()
+        };
+        let chunks: Vec<_> = pks.chunks(20).collect();
+        let ms = format!(
+            "and_v(v:{},and_v(v:{},and_v(v:{},and_v(v:{},{}))))",
+            multi(chunks[0]),
+            multi(chunks[1]),
+            multi(chunks[2]),
+            multi(chunks[3]),
+            multi(chunks[4]),
+        );
+        let desc = StdDescriptor::from_str(&format!("elwsh({})", ms)).unwrap();
+        assert_eq!(desc.script_size(), 5 * 684);
+        assert_eq!(desc.script_size(), desc.explicit_script().len());
+        desc.check_script_size().unwrap();
+
+        // Parsing already rejects scripts above the standardness limits, so
+        // build the oversized ones from their AST. Appending a 6-key multi
+        // takes the witness script from 3420 to 3627 bytes
+        let ms = Miniscript::from_str(&ms).unwrap();
+        let ms = Miniscript::from_ast(Terminal::AndV(
+            Arc::new(Miniscript::from_ast(Terminal::Verify(Arc::new(ms))).unwrap()),
+            Arc::new(Miniscript::from_ast(Terminal::Multi(1, pk_vec(&pks[..6]))).unwrap()),
+        ))
+        .unwrap();
+        let desc = Descriptor::Wsh(Wsh::new(ms).unwrap());
+        assert_eq!(desc.script_size(), 3627);
+        match desc.check_script_size() {
+            Err(Error::ScriptSizeTooLarge) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        // A 15-key multi fits in a 520-byte redeem script, a 16-key one does not
+        let sh = StdDescriptor::from_str(&format!("elsh({})", multi(&pks[..15]))).unwrap();
+        assert_eq!(sh.script_size(), 513);
+        sh.check_script_size().unwrap();
+        let ms = Miniscript::from_ast(Terminal::Multi(1, pk_vec(&pks[..16]))).unwrap();
+        let sh = Descriptor::Sh(Sh::new(ms).unwrap());
+        assert_eq!(sh.script_size(), 547);
+        match sh.check_script_size() {
+            Err(Error::ScriptSizeTooLarge) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        let pkh = StdDescriptor::from_str(&format!("elpkh({})", pks[0])).unwrap();
+        assert_eq!(pkh.script_size(), 25);
+        pkh.check_script_size().unwrap();
+    }
+
+    #[test]
+    fn max_exec_stack_items() {
+        let secp = bitcoin::secp256k1::Secp256k1::signing_only();
+        let pks: Vec<String> = (1..81u8)
+            .map(|i| {
+                let sk = bitcoin::secp256k1::SecretKey::from_slice(&[i; 32]).unwrap();
+                bitcoin::PublicKey {
+                    compressed: true,
+                    key: bitcoin::secp256k1::PublicKey::from_secret_key(&secp, &sk),
+                }
+                .to_string()
+            })
+            .collect();
+        // Four 20-key multis: the witness holds k + 1 elements for each, and
+        // the keys of a multi are pushed on top of that
+        let chunks: Vec<_> = pks.chunks(20).map(|c| c.join(",")).collect();
+        let multis = |last_k: usize| {
+            StdDescriptor::from_str(&format!(
+                "elwsh(and_v(v:multi(20,{}),and_v(v:multi(20,{}),and_v(v:multi(20,{}),multi({},{})))))",
+                chunks[0], chunks[1], chunks[2], last_k, chunks[3],
+            ))
+            .unwrap()
+        };
+        assert_eq!(multis(14).max_exec_stack_items().unwrap(), 100);
+        multis(14).check_exec_stack_items().unwrap();
+        assert_eq!(multis(15).max_exec_stack_items().unwrap(), 101);
+        match multis(15).check_exec_stack_items() {
+            Err(Error::StackSizeTooLarge(101)) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        let wpkh = StdDescriptor::from_str(&format!("elwpkh({})", pks[0])).unwrap();
+        assert_eq!(wpkh.max_exec_stack_items().unwrap(), 4);
+        // <dummy> <2 sigs> <2> <3 keys> <3>
+        let multi = StdDescriptor::from_str(&format!(
+            "elsh(wsh(sortedmulti(2,{},{},{})))",
+            pks[0], pks[1], pks[2]
+        ))
+        .unwrap();
+        assert_eq!(multi.max_exec_stack_items().unwrap(), 8);
+
+        // Legacy spends are only limited by consensus
+        let pkh = StdDescriptor::from_str(&format!("elpkh({})", pks[0])).unwrap();
+        assert_eq!(pkh.max_exec_stack_items().unwrap(), 4);
+        pkh.check_exec_stack_items().unwrap();
+    }
+
+    #[test]
+    fn update_pset_input() {
+        let pk = "020000000000000000000000000000000000000000000000000000000000000002";
+        let pk2 = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+        let multi = format!("multi(1,{},{})", pk, pk2);
+
+        let desc = StdDescriptor::from_str(&format!("elsh(wsh({}))", multi)).unwrap();
+        let mut input = elements::pset::Input::default();
+        desc.update_pset_input(&mut input);
+        let witness_script = desc.explicit_script();
+        assert_eq!(input.witness_script, Some(witness_script.clone()));
+        assert_eq!(input.redeem_script, Some(witness_script.to_v0_p2wsh()));
+        assert_eq!(
+            input.redeem_script.as_ref().unwrap().to_p2sh(),
+            desc.script_pubkey()
+        );
+
+        let desc = StdDescriptor::from_str(&format!("elsh({})", multi)).unwrap();
+        let mut input = elements::pset::Input::default();
+        desc.update_pset_input(&mut input);
+        assert_eq!(input.redeem_script, Some(desc.explicit_script()));
+        assert_eq!(input.witness_script, None);
+
+        let desc = StdDescriptor::from_str(&format!("elwpkh({})", pk)).unwrap();
+        let mut input = elements::pset::Input::default();
+        desc.update_pset_input(&mut input);
+        assert_eq!(input.redeem_script, None);
+        assert_eq!(input.witness_script, None);
+    }
+
+    #[test]
+    fn dissatisfy() {
+        let pk = "020000000000000000000000000000000000000000000000000000000000000002";
+        let pk2 = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+
+        let desc =
+            StdDescriptor::from_str(&format!("elwsh(or_d(pk({}),pk({})))", pk, pk2)).unwrap();
+        let (witness, script_sig) = desc.dissatisfy().unwrap();
+        assert_eq!(
+            witness,
+            vec![vec![], vec![], desc.explicit_script().into_bytes()]
+        );
+        assert_eq!(script_sig, Script::new());
+
+        let desc = StdDescriptor::from_str(&format!("elsh(or_d(pk({}),pk({})))", pk, pk2)).unwrap();
+        let (witness, script_sig) = desc.dissatisfy().unwrap();
+        assert!(witness.is_empty());
+        assert_eq!(
+            script_sig,
+            script::Builder::new()
+                .push_int(0)
+                .push_int(0)
+                .push_slice(&desc.explicit_script()[..])
+                .into_script()
+        );
+
+        let desc = StdDescriptor::from_str(&format!("elwpkh({})", pk)).unwrap();
+        let (witness, _) = desc.dissatisfy().unwrap();
+        assert_eq!(witness, vec![vec![], Vec::<u8>::from_hex(pk).unwrap()]);
+
+        // Fragments without a dissatisfaction
+        let desc =
+            StdDescriptor::from_str(&format!("elwsh(and_v(v:pk({}),pk({})))", pk, pk2)).unwrap();
+        assert_eq!(
+            desc.dissatisfy().unwrap_err().to_string(),
+            Error::CouldNotSatisfy.to_string()
+        );
+        let desc = StdDescriptor::from_str("elwsh(older(1000))").unwrap();
+        assert!(desc.dissatisfy().is_err());
+    }
+
+    #[test]
+    fn signable_paths() {
+        let secp = bitcoin::secp256k1::Secp256k1::signing_only();
+        let mut pks = vec![];
+        let mut keystore = HashMap::new();
+        for i in 1..4 {
+            let sk = secp256k1_zkp::SecretKey::from_slice(&[i; 32]).unwrap();
+            let pk = PublicKey {
+                compressed: true,
+                key: bitcoin::secp256k1::PublicKey::from_secret_key(&secp, &sk),
+            };
+            pks.push(pk);
+            if i != 2 {
+                keystore.insert(pk, sk);
+            }
+        }
+
+        let desc = Descriptor::new_wsh_sortedmulti(2, pks.clone()).unwrap();
+        assert_eq!(desc.lift().unwrap().spend_paths().len(), 3);
+        let paths = desc.signable_paths(&keystore).unwrap();
+        assert_eq!(paths.len(), 1);
+        let mut expected_keys = vec![pks[0].to_pubkeyhash(), pks[2].to_pubkeyhash()];
+        expected_keys.sort();
+        assert_eq!(paths[0].keys, expected_keys);
+        assert!(paths[0].conditions.is_empty());
+
+        // Timelocked branches are reported with their conditions
+        let desc = StdDescriptor::from_str(&format!(
+            "elwsh(or_d(pk({}),and_v(v:pk({}),older(144))))",
+            pks[1], pks[2]
+        ))
+        .unwrap();
+        let paths = desc.signable_paths(&keystore).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].keys, vec![pks[2].to_pubkeyhash()]);
+        assert_eq!(paths[0].conditions, vec![Semantic::Older(144)]);
+
+        keystore.clear();
+        assert!(desc.signable_paths(&keystore).unwrap().is_empty());
+    }
+
+    #[test]
+    fn nesting() {
+        let pk = "020000000000000000000000000000000000000000000000000000000000000002";
+        let multi = format!("sortedmulti(1,{})", pk);
+        let pk_hash = bitcoin::PublicKey::from_str(pk).unwrap().to_pubkeyhash();
+
+        for valid in &[
+            format!("elsh({})", multi),
+            format!("elsh(wsh({}))", multi),
+            format!("elsh(wsh(pk({})))", pk),
+            format!("elsh(wpkh({}))", pk),
+            format!("elsh(pk({}))", pk),
+            format!("elwsh({})", multi),
+            format!("elwsh(pkh({}))", pk_hash),
+        ] {
+            StdDescriptor::from_str(valid).unwrap();
+        }
+
+        for &(invalid, outer, inner) in &[
+            ("elsh(elsh(pk({})))", "elsh", "elsh"),
+            ("elsh(sh(pk({})))", "elsh", "sh"),
+            ("elsh(eltr({}))", "elsh", "eltr"),
+            ("elsh(tr({}))", "elsh", "tr"),
+            ("elsh(elwpkh({}))", "elsh", "elwpkh"),
+            ("elsh(wsh(wpkh({})))", "elsh(wsh)", "wpkh"),
+            ("elsh(wsh(wsh(pk({}))))", "elsh(wsh)", "wsh"),
+            ("elwsh(wpkh({}))", "elwsh", "wpkh"),
+            ("elwsh(elwsh(pk({})))", "elwsh", "elwsh"),
+            ("elwsh(sh(pk({})))", "elwsh", "sh"),
+            ("elcovwsh({0},elwpkh({0}))", "elcovwsh", "elwpkh"),
+        ] {
+            let desc = invalid.replace("{}", pk).replace("{0}", pk);
+            match StdDescriptor::from_str(&desc) {
+                Err(Error::InvalidNesting {
+                    outer: ref o,
+                    inner: ref i,
+                }) if o == outer && i == inner => {}
+                x => panic!("unexpected result {:?} for {}", x, desc),
+            }
+        }
+
+        let err = StdDescriptor::from_str(&format!("elsh(wsh(wpkh({})))", pk)).unwrap_err();
+        assert_eq!(err.to_string(), "cannot nest wpkh inside elsh(wsh)");
+    }
+
+    #[test]
+    fn uncompressed_legacy_keys() {
+        let uncompressed_pk =
+        "0414fc03b8df87cd7b872996810db8458d61da8448e531569c8517b469a119d267be5645686309c6e6736dbd93940707cc9143d3cf29f1b877ff340e2cb2d259cf";
+        let pk = bitcoin::PublicKey::from_str(uncompressed_pk).unwrap();
+        assert!(!pk.compressed);
+
+        let pkh = StdDescriptor::from_str(&format!("elpkh({})", uncompressed_pk)).unwrap();
+        let addr = pkh.address(&elements::AddressParams::ELEMENTS).unwrap();
+        assert_eq!(
+            addr,
+            elements::Address::p2pkh(&pk, None, &elements::AddressParams::ELEMENTS)
+        );
+        // The compressed serialization of the same key has a different hash
+        let compressed = bitcoin::PublicKey {
+            compressed: true,
+            key: pk.key,
+        };
+        assert_ne!(
+            addr,
+            elements::Address::p2pkh(&compressed, None, &elements::AddressParams::ELEMENTS)
+        );
+
+        // Compression is preserved when translating keys
+        let sh = Descriptor::<String>::from_str(&format!("elsh(pk({}))", uncompressed_pk))
+            .unwrap()
+            .translate_pk2(|s| bitcoin::PublicKey::from_str(s))
+            .unwrap();
+        assert_eq!(
+            sh,
+            StdDescriptor::from_str(&format!("elsh(pk({}))", uncompressed_pk)).unwrap()
+        );
+        assert_eq!(
+            sh.to_string().split('#').next().unwrap(),
+            format!("elsh(pk({}))", uncompressed_pk)
+        );
+        sh.address(&elements::AddressParams::ELEMENTS).unwrap();
+    }
+
     #[test]
     pub fn script_pubkey() {
         let bare = StdDescriptor::from_str(&format!(
@@ -1297,34 +2951,464 @@ mod tests {
     }
 
     #[test]
-    fn after_is_cltv() {
-        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str("elwsh(after(1000))").unwrap();
-        let script = descriptor.explicit_script();
-
-        let actual_instructions: Vec<_> = script.instructions().collect();
-        let check = actual_instructions.last().unwrap();
+    fn sh_multi() {
+        let secp = secp256k1_zkp::Secp256k1::new();
+        let msg = secp256k1_zkp::Message::from_slice(&b"michael was a message, amusingly"[..])
+            .expect("32 bytes");
+        let mut keys = vec![];
+        for i in 1..4 {
+            let sk = secp256k1_zkp::SecretKey::from_slice(&[i; 32]).unwrap();
+            let pk = bitcoin::PublicKey {
+                key: secp256k1_zkp::PublicKey::from_secret_key(&secp, &sk),
+                compressed: true,
+            };
+            keys.push((pk, secp.sign(&msg, &sk)));
+        }
+        keys.sort_by(|a, b| a.0.to_bytes().cmp(&b.0.to_bytes()));
+        // Use an order which differs from the sorted one
+        let (a, b, c) = (keys[2], keys[0], keys[1]);
 
-        assert_eq!(check, &Ok(Instruction::Op(OP_CLTV)))
+        let desc =
+            StdDescriptor::from_str(&format!("elsh(multi(2,{},{},{}))", a.0, b.0, c.0)).unwrap();
+        let redeem_script = script::Builder::new()
+            .push_int(2)
+            .push_key(&a.0)
+            .push_key(&b.0)
+            .push_key(&c.0)
+            .push_int(3)
+            .push_opcode(opcodes::all::OP_CHECKMULTISIG)
+            .into_script();
+        assert_eq!(desc.explicit_script(), redeem_script);
+        assert_eq!(desc.script_pubkey(), redeem_script.to_p2sh());
+        assert_eq!(
+            desc.address(&elements::AddressParams::ELEMENTS).unwrap(),
+            elements::Address::p2sh(&redeem_script, None, &elements::AddressParams::ELEMENTS)
+        );
+        let sorted =
+            StdDescriptor::from_str(&format!("elsh(sortedmulti(2,{},{},{}))", a.0, b.0, c.0))
+                .unwrap();
+        assert_ne!(sorted.script_pubkey(), desc.script_pubkey());
+
+        // Signatures must follow the order of the keys in the script,
+        // not the sorted order
+        let mut satisfier: HashMap<bitcoin::PublicKey, ElementsSig> = HashMap::new();
+        satisfier.insert(a.0, (a.1, elements::SigHashType::All));
+        satisfier.insert(c.0, (c.1, elements::SigHashType::All));
+        let (witness, script_sig) = desc.get_satisfaction(&satisfier).unwrap();
+        let mut sig_a = a.1.serialize_der().to_vec();
+        sig_a.push(0x01);
+        let mut sig_c = c.1.serialize_der().to_vec();
+        sig_c.push(0x01);
+        assert!(witness.is_empty());
+        assert_eq!(
+            script_sig,
+            script::Builder::new()
+                .push_int(0)
+                .push_slice(&sig_a)
+                .push_slice(&sig_c)
+                .push_slice(&redeem_script[..])
+                .into_script()
+        );
     }
 
     #[test]
-    fn older_is_csv() {
-        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str("elwsh(older(1000))").unwrap();
-        let script = descriptor.explicit_script();
+    fn pk_k_pk_h_fragments() {
+        let secp = secp256k1_zkp::Secp256k1::new();
+        let msg = secp256k1_zkp::Message::from_slice(&b"michael was a message, amusingly"[..])
+            .expect("32 bytes");
+        let mut keys = vec![];
+        for i in 1..3 {
+            let sk = secp256k1_zkp::SecretKey::from_slice(&[i; 32]).unwrap();
+            let pk = bitcoin::PublicKey {
+                key: secp256k1_zkp::PublicKey::from_secret_key(&secp, &sk),
+                compressed: true,
+            };
+            let mut sig = secp.sign(&msg, &sk).serialize_der().to_vec();
+            sig.push(0x01);
+            keys.push((pk, secp.sign(&msg, &sk), sig));
+        }
+        let (a, b) = (keys[0].clone(), keys[1].clone());
 
-        let actual_instructions: Vec<_> = script.instructions().collect();
-        let check = actual_instructions.last().unwrap();
+        // c:pk_k is the raw form of pk
+        let desc = StdDescriptor::from_str(&format!("elwsh(c:pk_k({}))", a.0)).unwrap();
+        assert_eq!(
+            desc,
+            StdDescriptor::from_str(&format!("elwsh(pk({}))", a.0)).unwrap()
+        );
+        let witness_script = script::Builder::new()
+            .push_key(&a.0)
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script();
+        assert_eq!(desc.explicit_script(), witness_script);
 
-        assert_eq!(check, &Ok(Instruction::Op(OP_CSV)))
-    }
+        let mut satisfier: HashMap<bitcoin::PublicKey, ElementsSig> = HashMap::new();
+        satisfier.insert(a.0, (a.1, elements::SigHashType::All));
+        let (witness, script_sig) = desc.get_satisfaction(&satisfier).unwrap();
+        assert_eq!(witness, vec![a.2.clone(), witness_script.into_bytes()]);
+        assert_eq!(script_sig, Script::new());
+        assert!(desc.get_satisfaction(()).is_err());
 
-    #[test]
-    fn roundtrip_tests() {
-        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str("elmulti");
+        // c:pk_h needs the key itself from the satisfier
+        let desc =
+            StdDescriptor::from_str(&format!("elwsh(c:pk_h({}))", a.0.to_pubkeyhash())).unwrap();
+        let witness_script = script::Builder::new()
+            .push_opcode(opcodes::all::OP_DUP)
+            .push_opcode(opcodes::all::OP_HASH160)
+            .push_slice(&a.0.to_pubkeyhash()[..])
+            .push_opcode(opcodes::all::OP_EQUALVERIFY)
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script();
+        assert_eq!(desc.explicit_script(), witness_script);
+        assert!(desc.get_satisfaction(&satisfier).is_err());
+
+        let mut pkh_satisfier: HashMap<hash160::Hash, (bitcoin::PublicKey, ElementsSig)> =
+            HashMap::new();
+        pkh_satisfier.insert(
+            a.0.to_pubkeyhash(),
+            (a.0, (a.1, elements::SigHashType::All)),
+        );
+        pkh_satisfier.insert(
+            b.0.to_pubkeyhash(),
+            (b.0, (b.1, elements::SigHashType::All)),
+        );
+        let (witness, _) = desc.get_satisfaction(&pkh_satisfier).unwrap();
         assert_eq!(
-            descriptor.unwrap_err().to_string(),
-            "unexpected «no arguments given»"
-        )
+            witness,
+            vec![a.2.clone(), a.0.to_bytes(), witness_script.into_bytes()]
+        );
+
+        // Both fragments are dissatisfiable by an empty signature
+        let desc = StdDescriptor::from_str(&format!(
+            "elwsh(or_d(c:pk_k({}),c:pk_h({})))",
+            a.0,
+            b.0.to_pubkeyhash()
+        ))
+        .unwrap();
+        let mut satisfier: HashMap<hash160::Hash, (bitcoin::PublicKey, ElementsSig)> =
+            HashMap::new();
+        satisfier.insert(
+            b.0.to_pubkeyhash(),
+            (b.0, (b.1, elements::SigHashType::All)),
+        );
+        let (witness, _) = desc.get_satisfaction(&satisfier).unwrap();
+        assert_eq!(
+            witness,
+            vec![
+                b.2.clone(),
+                b.0.to_bytes(),
+                vec![],
+                desc.explicit_script().into_bytes()
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_signatures() {
+        let secp = secp256k1_zkp::Secp256k1::new();
+        let msg = secp256k1_zkp::Message::from_slice(&b"michael was a message, amusingly"[..])
+            .expect("32 bytes");
+        let mut keys = vec![];
+        for i in 1..4 {
+            let sk = secp256k1_zkp::SecretKey::from_slice(&[i; 32]).unwrap();
+            let pk = bitcoin::PublicKey {
+                key: secp256k1_zkp::PublicKey::from_secret_key(&secp, &sk),
+                compressed: true,
+            };
+            keys.push((pk, secp.sign(&msg, &sk)));
+        }
+        let (a, b, c) = (keys[0], keys[1], keys[2]);
+
+        let desc =
+            StdDescriptor::from_str(&format!("elwsh(multi(2,{},{},{}))", a.0, b.0, c.0)).unwrap();
+        let mut satisfier: HashMap<bitcoin::PublicKey, ElementsSig> = HashMap::new();
+        satisfier.insert(a.0, (a.1, elements::SigHashType::All));
+        satisfier.insert(c.0, (c.1, elements::SigHashType::All));
+        let (witness, script_sig) = desc.get_satisfaction(&satisfier).unwrap();
+
+        let verify_sig =
+            |pk: &bitcoin::PublicKey, (sig, _)| secp.verify(&msg, &sig, &pk.key).is_ok();
+        assert_eq!(
+            desc.extract_signatures(&script_sig, &witness, 0, 0xffffffff, verify_sig)
+                .unwrap(),
+            vec![
+                (a.0, (a.1, elements::SigHashType::All)),
+                (c.0, (c.1, elements::SigHashType::All)),
+            ]
+        );
+
+        // The witness of another descriptor does not satisfy this one
+        let other =
+            StdDescriptor::from_str(&format!("elwsh(multi(2,{},{},{}))", b.0, a.0, c.0)).unwrap();
+        assert!(other
+            .extract_signatures(&script_sig, &witness, 0, 0xffffffff, verify_sig)
+            .is_err());
+    }
+
+    #[test]
+    fn complete_scriptsig() {
+        let secp = secp256k1_zkp::Secp256k1::new();
+        let msg = secp256k1_zkp::Message::from_slice(&b"michael was a message, amusingly"[..])
+            .expect("32 bytes");
+        let mut pks = vec![];
+        let mut sigs = vec![];
+        for i in 1..4 {
+            let sk = secp256k1_zkp::SecretKey::from_slice(&[i; 32]).unwrap();
+            pks.push(bitcoin::PublicKey {
+                key: secp256k1_zkp::PublicKey::from_secret_key(&secp, &sk),
+                compressed: true,
+            });
+            let mut sig = secp.sign(&msg, &sk).serialize_der().to_vec();
+            sig.push(0x01);
+            sigs.push(sig);
+        }
+        let desc =
+            StdDescriptor::from_str(&format!("elsh(multi(2,{},{},{}))", pks[0], pks[1], pks[2]))
+                .unwrap();
+
+        struct CompleteSat {
+            id: [u8; 32],
+            script_sig: Script,
+        }
+
+        impl Satisfier<bitcoin::PublicKey> for CompleteSat {
+            fn lookup_complete_scriptsig(&self, id: &[u8; 32]) -> Option<Script> {
+                if *id == self.id {
+                    Some(self.script_sig.clone())
+                } else {
+                    None
+                }
+            }
+        }
+
+        // A valid 2-of-3 scriptSig is used verbatim, without any signatures
+        // being available individually
+        let script_sig = script::Builder::new()
+            .push_int(0)
+            .push_slice(&sigs[0])
+            .push_slice(&sigs[2])
+            .push_slice(&desc.explicit_script()[..])
+            .into_script();
+        let satisfier = CompleteSat {
+            id: desc.id(),
+            script_sig: script_sig.clone(),
+        };
+        assert_eq!(
+            desc.get_satisfaction(&satisfier).unwrap(),
+            (vec![], script_sig)
+        );
+
+        // A scriptSig with only one of the two signatures is rejected
+        let satisfier = CompleteSat {
+            id: desc.id(),
+            script_sig: script::Builder::new()
+                .push_int(0)
+                .push_slice(&sigs[0])
+                .push_slice(&desc.explicit_script()[..])
+                .into_script(),
+        };
+        assert!(desc.get_satisfaction(&satisfier).is_err());
+
+        // So is one which does not end with this descriptor's redeem script
+        let other =
+            StdDescriptor::from_str(&format!("elsh(multi(1,{},{}))", pks[0], pks[1])).unwrap();
+        let satisfier = CompleteSat {
+            id: desc.id(),
+            script_sig: script::Builder::new()
+                .push_int(0)
+                .push_slice(&sigs[0])
+                .push_slice(&other.explicit_script()[..])
+                .into_script(),
+        };
+        assert!(desc.get_satisfaction(&satisfier).is_err());
+
+        // Satisfiers which do not know the descriptor are ignored
+        let satisfier = CompleteSat {
+            id: [0; 32],
+            script_sig: Script::new(),
+        };
+        assert!(desc.get_satisfaction(&satisfier).is_err());
+    }
+
+    #[test]
+    fn max_depth() {
+        let pk = "020000000000000000000000000000000000000000000000000000000000000002";
+        let deep = format!(
+            "elwsh({}pk({}){})",
+            "or_i(0,".repeat(10000),
+            pk,
+            ")".repeat(10000)
+        );
+        match StdDescriptor::from_str(&deep) {
+            Err(Error::MaxRecursiveDepthExceeded) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        let shallow = format!(
+            "elwsh(or_i(pk({}),or_i(pk({}),pk({}))))",
+            pk,
+            "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7",
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+        assert!(StdDescriptor::from_str(&shallow).is_ok());
+        assert!(StdDescriptor::from_str_with_max_depth(&shallow, 5).is_ok());
+        match StdDescriptor::from_str_with_max_depth(&shallow, 4) {
+            Err(Error::MaxRecursiveDepthExceeded) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn hash_locks() {
+        let pk_a = "020000000000000000000000000000000000000000000000000000000000000002";
+        let pk_b = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+        let h1 = "1111111111111111111111111111111111111111111111111111111111111111";
+        let h2 = "2222222222222222222222222222222222222222";
+        let desc = StdDescriptor::from_str(&format!(
+            "elwsh(or_i(and_v(v:pk({}),sha256({})),and_v(v:pk({}),hash160({}))))",
+            pk_a, h1, pk_b, h2
+        ))
+        .unwrap();
+        assert_eq!(
+            desc.hash_locks(),
+            vec![
+                super::HashLock::Sha256(sha256::Hash::from_hex(h1).unwrap()),
+                super::HashLock::Hash160(hash160::Hash::from_hex(h2).unwrap()),
+            ]
+        );
+
+        let desc = StdDescriptor::from_str(&format!("elwpkh({})", pk_a)).unwrap();
+        assert!(desc.hash_locks().is_empty());
+    }
+
+    #[test]
+    fn input_fee() {
+        let pk_a = "020000000000000000000000000000000000000000000000000000000000000002";
+        let pk_b = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+        let pk_c = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let wpkh = StdDescriptor::from_str(&format!("elwpkh({})", pk_a)).unwrap();
+        let multi =
+            StdDescriptor::from_str(&format!("elwsh(sortedmulti(2,{},{},{}))", pk_a, pk_b, pk_c))
+                .unwrap();
+
+        // 160 + 3 + 112 weight units, i.e. 69 vbytes
+        assert_eq!(wpkh.input_fee(1.0).unwrap(), 69);
+        // 160 + 3 + 258 weight units, i.e. 106 vbytes
+        assert_eq!(multi.input_fee(1.0).unwrap(), 106);
+        // Fees are rounded up at the Liquid default feerate
+        assert_eq!(wpkh.input_fee(0.1).unwrap(), 7);
+        assert_eq!(multi.input_fee(0.1).unwrap(), 11);
+        assert_eq!(wpkh.input_fee(0.0).unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "use-serde")]
+    fn to_json_tree() {
+        let pk_a = "020000000000000000000000000000000000000000000000000000000000000002";
+        let pk_b = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+        let desc = StdDescriptor::from_str(&format!(
+            "elwsh(or_d(pk({}),and_v(v:pk({}),older(144))))",
+            pk_a, pk_b
+        ))
+        .unwrap();
+        let expected = format!(
+            "{{\"args\":[{{\"args\":[\
+             {{\"args\":[{{\"fragment\":\"pk_k\",\"keys\":[\"{a}\"],\"type\":\"K\"}}],\"fragment\":\"c\",\"type\":\"B\"}},\
+             {{\"args\":[\
+             {{\"args\":[{{\"args\":[{{\"fragment\":\"pk_k\",\"keys\":[\"{b}\"],\"type\":\"K\"}}],\"fragment\":\"c\",\"type\":\"B\"}}],\"fragment\":\"v\",\"type\":\"V\"}},\
+             {{\"fragment\":\"older\",\"type\":\"B\",\"value\":144}}\
+             ],\"fragment\":\"and_v\",\"type\":\"B\"}}\
+             ],\"fragment\":\"or_d\",\"type\":\"B\"}}],\"fragment\":\"elwsh\"}}",
+            a = pk_a,
+            b = pk_b,
+        );
+        assert_eq!(desc.to_json_tree().to_string(), expected);
+
+        let desc = StdDescriptor::from_str(&format!("elsh(wsh(sortedmulti(1,{},{})))", pk_a, pk_b))
+            .unwrap();
+        assert_eq!(
+            desc.to_json_tree().to_string(),
+            format!(
+                "{{\"args\":[{{\"args\":[{{\"fragment\":\"sortedmulti\",\"k\":1,\"keys\":[\"{}\",\"{}\"]}}],\"fragment\":\"wsh\"}}],\"fragment\":\"elsh\"}}",
+                pk_a, pk_b
+            )
+        );
+    }
+
+    #[test]
+    fn sighash_context() {
+        let pk = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+        let context = |desc: String| StdDescriptor::from_str(&desc).unwrap().sighash_context();
+        assert_eq!(
+            context(format!("elpkh({})", pk)),
+            super::SighashContext::Legacy
+        );
+        assert_eq!(
+            context(format!("elsh(pk({}))", pk)),
+            super::SighashContext::Legacy
+        );
+        assert_eq!(
+            context(format!("elwsh(pk({}))", pk)),
+            super::SighashContext::SegwitV0
+        );
+        assert_eq!(
+            context(format!("elsh(wpkh({}))", pk)),
+            super::SighashContext::SegwitV0
+        );
+        assert_eq!(
+            context(format!("elcovwsh({},pk({}))", pk, pk)),
+            super::SighashContext::SegwitV0
+        );
+    }
+
+    #[test]
+    fn output_weight() {
+        let desc = StdDescriptor::from_str(
+            "elwpkh(03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7)",
+        )
+        .unwrap();
+        let txout = elements::TxOut {
+            asset: elements::confidential::Asset::Explicit(
+                elements::AssetId::from_slice(&[1; 32]).unwrap(),
+            ),
+            value: elements::confidential::Value::Explicit(1000),
+            nonce: elements::confidential::Nonce::Null,
+            script_pubkey: desc.script_pubkey(),
+            witness: elements::TxOutWitness::default(),
+        };
+        assert_eq!(elements::encode::serialize(&txout).len(), 66);
+        assert_eq!(desc.output_weight(false), 4 * 66 + 2);
+        assert_eq!(desc.output_weight(true), 4 * 122 + 1 + 131 + 3 + 4174);
+    }
+
+    #[test]
+    fn after_is_cltv() {
+        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str("elwsh(after(1000))").unwrap();
+        let script = descriptor.explicit_script();
+
+        let actual_instructions: Vec<_> = script.instructions().collect();
+        let check = actual_instructions.last().unwrap();
+
+        assert_eq!(check, &Ok(Instruction::Op(OP_CLTV)))
+    }
+
+    #[test]
+    fn older_is_csv() {
+        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str("elwsh(older(1000))").unwrap();
+        let script = descriptor.explicit_script();
+
+        let actual_instructions: Vec<_> = script.instructions().collect();
+        let check = actual_instructions.last().unwrap();
+
+        assert_eq!(check, &Ok(Instruction::Op(OP_CSV)))
+    }
+
+    #[test]
+    fn roundtrip_tests() {
+        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str("elmulti");
+        assert_eq!(
+            descriptor.unwrap_err().to_string(),
+            "unexpected «no arguments given»"
+        )
     }
 
     #[test]
@@ -1336,6 +3420,101 @@ mod tests {
         )
     }
 
+    #[test]
+    fn reused_keys() {
+        let desc =
+            Descriptor::<String>::from_str("elwsh(or_d(pk(A),and_v(v:pk(A),older(144))))").unwrap();
+        assert_eq!(desc.reused_keys(), vec!["A".to_owned()]);
+        assert!(desc.sanity_check().is_err());
+
+        let desc =
+            Descriptor::<String>::from_str("elwsh(or_d(pk(A),and_v(v:pk(B),older(144))))").unwrap();
+        assert!(desc.reused_keys().is_empty());
+        let desc = Descriptor::<String>::from_str("elwsh(multi(1,A,B,A,B,C))").unwrap();
+        assert_eq!(desc.reused_keys(), vec!["A".to_owned(), "B".to_owned()]);
+    }
+
+    #[test]
+    fn check_satisfiable() {
+        let desc = |s: &str| Descriptor::<String>::from_str(s).unwrap();
+
+        desc("elwsh(or_d(pk(A),and_v(v:pk(B),older(144))))")
+            .check_satisfiable()
+            .unwrap();
+        // Two height locks can be satisfied together
+        desc("elwsh(and_v(v:pk(A),and_v(v:after(100),after(200))))")
+            .check_satisfiable()
+            .unwrap();
+        // but a height and a time lock cannot
+        let err = desc("elwsh(and_v(v:pk(A),and_v(v:after(100),after(500000001))))")
+            .check_satisfiable()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            Error::LiftError(LiftError::HeightTimeLockCombination).to_string()
+        );
+        // Only two of the three subexpressions can be satisfied
+        match desc("elwsh(thresh(3,pk(A),s:pk(B),a:0))").check_satisfiable() {
+            Err(Error::BadDescriptor(..)) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+        desc("elwsh(thresh(2,pk(A),s:pk(B),a:0))")
+            .check_satisfiable()
+            .unwrap();
+    }
+
+    #[test]
+    fn placeholder_keys() {
+        // `String` implements `MiniscriptKey` and `FromStr`, so it already
+        // serves as the key type for test vectors with letter placeholders
+        let s = "elwsh(and_v(v:pk(A),pk(B)))";
+        let desc = Descriptor::<String>::from_str(s).unwrap();
+        assert_eq!(desc.to_string().split('#').next().unwrap(), s);
+        let mut keys = vec![];
+        desc.for_each_key(|key| {
+            if let ForEach::Key(pk) = key {
+                keys.push(pk.clone());
+            }
+            true
+        });
+        assert_eq!(keys, vec!["A".to_owned(), "B".to_owned()]);
+
+        // whereas `DummyKey` only stands for the empty string
+        assert!(Descriptor::<DummyKey>::from_str(s).is_err());
+    }
+
+    #[test]
+    fn instantiate() {
+        let template = "elwsh(or_d(pk(A),and_v(v:pk(B),older(@delay))))";
+        let mut params = HashMap::new();
+        params.insert("delay".to_owned(), 144);
+        let desc = Descriptor::<String>::instantiate(template, &params).unwrap();
+        assert_eq!(
+            desc,
+            Descriptor::<String>::from_str("elwsh(or_d(pk(A),and_v(v:pk(B),older(144))))").unwrap()
+        );
+        params.insert("delay".to_owned(), 1008);
+        let desc = Descriptor::<String>::instantiate(template, &params).unwrap();
+        assert_eq!(
+            desc.to_string().split('#').next().unwrap(),
+            "elwsh(or_d(pk(A),and_v(v:pk(B),older(1008))))"
+        );
+
+        // all placeholders must be filled
+        let template = "elwsh(and_v(v:pk(A),and_v(v:after(@expiry),older(@delay))))";
+        assert_eq!(
+            Descriptor::<String>::instantiate(template, &params)
+                .unwrap_err()
+                .to_string(),
+            "Invalid descriptor: no value for template parameter @expiry"
+        );
+        params.insert("expiry".to_owned(), 700_000);
+        Descriptor::<String>::instantiate(template, &params).unwrap();
+
+        // only timelocks can be parameters
+        assert!(Descriptor::<String>::instantiate("elwsh(pk(@key))", &params).is_err());
+    }
+
     #[test]
     fn witness_stack_for_andv_is_arranged_in_correct_order() {
         // arrange
@@ -1663,6 +3842,61 @@ pk(03f28773c2d975288bc7d1d205c3748651b075fbc6610e58cddeeddf8f19405aa8))";
         assert_eq!(descriptor_str, descriptor.to_string_with_secret(&keymap));
     }
 
+    #[test]
+    fn key_map_satisfier() {
+        use super::KeyMapSatisfier;
+
+        let secp = secp256k1_zkp::Secp256k1::new();
+        let msg = secp256k1_zkp::Message::from_slice(&b"michael was a message, amusingly"[..])
+            .expect("32 bytes");
+
+        // WIF keys are replaced by their public key
+        let (desc, keymap) = Descriptor::<DescriptorPublicKey>::parse_descriptor(
+            &secp,
+            "elwpkh(KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn)",
+        )
+        .unwrap();
+        assert_eq!(
+            desc.to_string().split('#').next().unwrap(),
+            "elwpkh(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)"
+        );
+        assert_eq!(keymap.len(), 1);
+
+        let satisfier = KeyMapSatisfier::new(&secp, &keymap, 0, msg, elements::SigHashType::All);
+        let definite = desc.at_derivation_index(0).unwrap();
+        let (witness, script_sig) = definite.get_satisfaction(&satisfier).unwrap();
+        assert_eq!(script_sig, Script::new());
+        assert_eq!(witness.len(), 2);
+        let pk = PublicKey::from_slice(&witness[1]).unwrap();
+        assert_eq!(
+            pk.to_string(),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+        assert_eq!(witness[0].last(), Some(&0x01));
+        let sig = secp256k1_zkp::Signature::from_der(&witness[0][..witness[0].len() - 1]).unwrap();
+        assert!(secp.verify(&msg, &sig, &pk.key).is_ok());
+
+        // Wildcard xprvs sign at any index
+        let (desc, keymap) = Descriptor::<DescriptorPublicKey>::parse_descriptor(&secp, "elsh(multi(1,xprv9s21ZrQH143K4CTb63EaMxja1YiTnSEWKMbn23uoEnAzxjdUJRQkazCAtzxGm4LSoTSVTptoV9RbchnKPW9HxKtZumdyxyikZFDLhogJ5Uj/44'/0'/0'/0/*,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))").unwrap();
+        for index in 0..3 {
+            let satisfier =
+                KeyMapSatisfier::new(&secp, &keymap, index, msg, elements::SigHashType::All);
+            let definite = desc.at_derivation_index(index).unwrap();
+            assert!(definite.get_satisfaction(&satisfier).is_ok());
+            // The satisfier only signs at the index it was created for
+            let other = desc.at_derivation_index(index + 1).unwrap();
+            assert!(other.get_satisfaction(&satisfier).is_err());
+        }
+
+        // Keys without a secret cannot be signed for
+        let desc = Descriptor::<DescriptorPublicKey>::from_str(
+            "elwpkh(03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7)",
+        )
+        .unwrap();
+        let definite = desc.at_derivation_index(0).unwrap();
+        assert!(definite.get_satisfaction(&satisfier).is_err());
+    }
+
     #[test]
     fn checksum_for_nested_sh() {
         let descriptor_str = "elsh(wpkh(xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL))";
@@ -1673,4 +3907,544 @@ pk(03f28773c2d975288bc7d1d205c3748651b075fbc6610e58cddeeddf8f19405aa8))";
         let descriptor: Descriptor<DescriptorPublicKey> = descriptor_str.parse().unwrap();
         assert_eq!(descriptor.to_string(), "elsh(wsh(pk(xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL)))#pqs0de7e");
     }
+
+    #[test]
+    fn find_key_by_fingerprint() {
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(2,[aabbccdd/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*,[deadbeef/48'/1'/0'/2']tpubDBrgjcxBxnXyL575sHdkpKohWu5qHKoQ7TJXKNrYznh5fVEGBv89hA8ENW7A8MFVpFUSvgLqc4Nj1WZcpePX6rrxviVtPowvMuGF5rdT2Vi/0/*))").unwrap();
+
+        let fingerprint = bip32::Fingerprint::from_str("deadbeef").unwrap();
+        let (path, key) = desc.find_key_by_fingerprint(fingerprint).unwrap();
+        assert_eq!(
+            path,
+            bip32::DerivationPath::from_str("m/48'/1'/0'/2'/0").unwrap()
+        );
+        assert_eq!(key.master_fingerprint(), fingerprint);
+        assert_eq!(key.to_string(), "[deadbeef/48'/1'/0'/2']tpubDBrgjcxBxnXyL575sHdkpKohWu5qHKoQ7TJXKNrYznh5fVEGBv89hA8ENW7A8MFVpFUSvgLqc4Nj1WZcpePX6rrxviVtPowvMuGF5rdT2Vi/0/*");
+
+        let unknown = bip32::Fingerprint::from_str("01020304").unwrap();
+        assert!(desc.find_key_by_fingerprint(unknown).is_none());
+    }
+
+    #[test]
+    fn multiline_descriptor() {
+        let pk1 = "020000000000000000000000000000000000000000000000000000000000000002";
+        let pk2 = "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7";
+        let canonical_str = format!("elwsh(multi(2,{},{}))", pk1, pk2);
+        let canonical = StdDescriptor::from_str(&canonical_str).unwrap();
+        let multiline = format!("elwsh(\n  multi(2,\n    {},\n    {}))", pk1, pk2);
+        assert_eq!(StdDescriptor::from_str(&multiline).unwrap(), canonical);
+
+        // The checksum is that of the whitespace-free descriptor
+        let checksum = desc_checksum(&canonical_str).unwrap();
+        assert_eq!(
+            canonical.to_string(),
+            format!("{}#{}", canonical_str, checksum)
+        );
+        let with_checksum = format!("{}#{}", multiline, checksum);
+        assert_eq!(StdDescriptor::from_str(&with_checksum).unwrap(), canonical);
+    }
+
+    #[test]
+    fn descriptor_id() {
+        let xpub = "tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr";
+        let desc0 = format!("elwpkh({}/0/*)", xpub);
+        let desc1 = format!("elwpkh({}/1/*)", xpub);
+        let id0 = Descriptor::<DescriptorPublicKey>::from_str(&desc0)
+            .unwrap()
+            .id();
+        let id1 = Descriptor::<DescriptorPublicKey>::from_str(&desc1)
+            .unwrap()
+            .id();
+        assert_ne!(id0, id1);
+        assert_eq!(id0, sha256::Hash::hash(desc0.as_bytes()).into_inner());
+
+        // Parsing the same descriptor again, with or without checksum,
+        // gives the same id
+        let reparsed = Descriptor::<DescriptorPublicKey>::from_str(&desc0).unwrap();
+        assert_eq!(reparsed.id(), id0);
+        let with_checksum =
+            Descriptor::<DescriptorPublicKey>::from_str(&reparsed.to_string()).unwrap();
+        assert_eq!(with_checksum.id(), id0);
+    }
+
+    #[test]
+    fn at_derivation_index() {
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*)").unwrap();
+        let definite = desc.at_derivation_index(3).unwrap();
+        assert_eq!(
+            definite.script_pubkey(),
+            desc.derived_script_pubkey(&secp, 3).unwrap()
+        );
+        assert_eq!(definite.to_string(), desc.derive(3).to_string());
+
+        let hardened = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0'/*)").unwrap();
+        assert_eq!(
+            hardened.at_derivation_index(3),
+            Err(ConversionError::HardenedChild)
+        );
+    }
+
+    #[test]
+    fn into_single_descriptors() {
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwpkh([d34db33f/84'/1'/0']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/*)").unwrap();
+        assert!(desc.is_multipath());
+        assert_eq!(
+            desc.to_string().split('#').next().unwrap(),
+            "elwpkh([d34db33f/84'/1'/0']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/*)"
+        );
+        assert_eq!(
+            desc.clone().into_single_descriptors().unwrap(),
+            vec![
+                Descriptor::<DescriptorPublicKey>::from_str("elwpkh([d34db33f/84'/1'/0']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*)").unwrap(),
+                Descriptor::<DescriptorPublicKey>::from_str("elwpkh([d34db33f/84'/1'/0']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/1/*)").unwrap(),
+            ]
+        );
+        // Multipath keys cannot be turned into a single public key
+        assert_eq!(desc.at_derivation_index(0), Err(ConversionError::MultiKey));
+
+        // Single-path descriptors expand to themselves
+        let single = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*)").unwrap();
+        assert!(!single.is_multipath());
+        assert_eq!(
+            single.clone().into_single_descriptors().unwrap(),
+            vec![single]
+        );
+
+        // All multipath keys must have the same number of paths
+        let mismatched = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(1,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/<0;1>/*,xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/<0;1;2>/*))").unwrap();
+        assert!(mismatched.into_single_descriptors().is_err());
+        // Only one multipath step per key
+        assert!(Descriptor::<DescriptorPublicKey>::from_str("elwpkh(tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/<2;3>/*)").is_err());
+    }
+
+    #[test]
+    fn check_multipath_key_reuse() {
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(2,[d34db33f/84'/1'/0']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/*,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))").unwrap();
+        let fixed = DescriptorPublicKey::from_str(
+            "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7",
+        )
+        .unwrap();
+        assert_eq!(
+            desc.check_multipath_key_reuse(),
+            vec![Warning::MultipathKeyReuse(fixed.clone())]
+        );
+        // The fixed key is indeed in both expanded descriptors
+        for single in desc.into_single_descriptors().unwrap() {
+            assert!(single.for_any_key(|key| *key.as_key() == fixed));
+        }
+
+        // Single-path xpubs are reused too, and each key is reported once
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwsh(or_d(multi(1,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/<0;1>/*,xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/*),pk(xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/*)))").unwrap();
+        assert_eq!(
+            desc.check_multipath_key_reuse(),
+            vec![Warning::MultipathKeyReuse(
+                DescriptorPublicKey::from_str("xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/*").unwrap()
+            )]
+        );
+
+        // Without multipath keys there are no sibling paths
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(2,tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))").unwrap();
+        assert!(desc.check_multipath_key_reuse().is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        let keys = [
+            "020000000000000000000000000000000000000000000000000000000000000002",
+            "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7",
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        ];
+        let old = StdDescriptor::from_str(&format!(
+            "elwsh(sortedmulti(2,{},{},{}))",
+            keys[0], keys[1], keys[2]
+        ))
+        .unwrap();
+        let new = StdDescriptor::from_str(&format!(
+            "elwsh(sortedmulti(2,{},{},{},{}))",
+            keys[2], keys[1], keys[0], keys[3]
+        ))
+        .unwrap();
+        let diff = old.diff(&new).unwrap();
+        assert_eq!(diff.added_keys, vec![PublicKey::from_str(keys[3]).unwrap()]);
+        assert!(diff.removed_keys.is_empty());
+        assert!(diff.changed_thresholds.is_empty());
+        assert!(!diff.is_empty());
+        assert_eq!(
+            new.diff(&old).unwrap().removed_keys,
+            vec![PublicKey::from_str(keys[3]).unwrap()]
+        );
+        // Key order does not matter
+        let reordered = StdDescriptor::from_str(&format!(
+            "elwsh(sortedmulti(2,{},{},{}))",
+            keys[2], keys[0], keys[1]
+        ))
+        .unwrap();
+        assert!(old.diff(&reordered).unwrap().is_empty());
+
+        // Bumped threshold and timelock
+        let old = StdDescriptor::from_str(&format!(
+            "elwsh(or_d(multi(2,{},{},{}),and_v(v:pk({}),older(144))))",
+            keys[0], keys[1], keys[2], keys[3]
+        ))
+        .unwrap();
+        let new = StdDescriptor::from_str(&format!(
+            "elwsh(or_d(multi(3,{},{},{}),and_v(v:pk({}),older(1008))))",
+            keys[0], keys[1], keys[2], keys[3]
+        ))
+        .unwrap();
+        let diff = old.diff(&new).unwrap();
+        assert!(diff.added_keys.is_empty());
+        assert!(diff.removed_keys.is_empty());
+        assert_eq!(diff.changed_thresholds, vec![(2, 3)]);
+        assert!(diff.changed_after.is_empty());
+        assert_eq!(diff.changed_older, vec![(144, 1008)]);
+
+        // Incompatible shapes
+        let wpkh = StdDescriptor::from_str(&format!("elwpkh({})", keys[0])).unwrap();
+        assert!(old.diff(&wpkh).is_err());
+        let no_timelock = StdDescriptor::from_str(&format!(
+            "elwsh(or_d(multi(2,{},{},{}),pk({})))",
+            keys[0], keys[1], keys[2], keys[3]
+        ))
+        .unwrap();
+        assert!(old.diff(&no_timelock).is_err());
+    }
+
+    #[test]
+    fn new_multisig_wallet() {
+        let xpubs: Vec<DescriptorPublicKey> = [
+            "[d34db33f/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/*",
+            "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/<0;1>/*",
+            "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/<0;1>/*",
+        ]
+        .iter()
+        .map(|s| DescriptorPublicKey::from_str(s).unwrap())
+        .collect();
+
+        let desc =
+            Descriptor::new_multisig_wallet(2, xpubs.clone(), DescriptorType::WshSortedMulti)
+                .unwrap();
+        let expected = format!(
+            "elwsh(sortedmulti(2,{},{},{}))",
+            xpubs[0], xpubs[1], xpubs[2]
+        );
+        assert_eq!(desc.to_string_no_chksum(), expected);
+        assert_eq!(
+            desc.to_string(),
+            format!("{}#{}", expected, desc_checksum(&expected).unwrap())
+        );
+        assert_eq!(desc, Descriptor::from_str(&desc.to_string()).unwrap());
+
+        let desc =
+            Descriptor::new_multisig_wallet(2, xpubs.clone(), DescriptorType::ShWshSortedMulti)
+                .unwrap();
+        assert_eq!(desc.desc_type(), DescriptorType::ShWshSortedMulti);
+
+        // Distinct paths from the same xpub are the same cosigner
+        let mut dup = xpubs.clone();
+        dup[2] = DescriptorPublicKey::from_str("xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/2/*").unwrap();
+        assert!(Descriptor::new_multisig_wallet(2, dup, DescriptorType::WshSortedMulti).is_err());
+        assert!(Descriptor::new_multisig_wallet(2, xpubs.clone(), DescriptorType::Wsh).is_err());
+        assert!(Descriptor::new_multisig_wallet(4, xpubs, DescriptorType::WshSortedMulti).is_err());
+    }
+
+    #[test]
+    fn change_descriptor() {
+        let receive = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(2,[d34db33f/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))").unwrap();
+        let change = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(2,[d34db33f/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/1/*,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))").unwrap();
+        assert_eq!(receive.change_descriptor(), Some(change.clone()));
+
+        // Multipath descriptors give their second path
+        let multipath = Descriptor::<DescriptorPublicKey>::from_str("elwsh(multi(2,[d34db33f/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/*,03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))").unwrap();
+        assert_eq!(multipath.change_descriptor(), Some(change.clone()));
+
+        // No receive path to replace
+        assert_eq!(change.change_descriptor(), None);
+        let hardened = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/0/*')").unwrap();
+        assert_eq!(hardened.change_descriptor(), None);
+        let single = Descriptor::<DescriptorPublicKey>::from_str(
+            "elwpkh(03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7)",
+        )
+        .unwrap();
+        assert_eq!(single.change_descriptor(), None);
+    }
+
+    #[test]
+    fn to_wallet_policy() {
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwsh(sortedmulti(2,[d34db33f/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr/<0;1>/*,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/<0;1>/*,[12345678/48'/1'/0'/2']xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/<2;3>/*))").unwrap();
+        let (template, keys) = desc.to_wallet_policy();
+        assert_eq!(template, "elwsh(sortedmulti(2,@0/**,@1/**,@2/<2;3>/*))");
+        assert_eq!(
+            keys.iter().map(|k| k.to_string()).collect::<Vec<_>>(),
+            vec![
+                "[d34db33f/48'/1'/0'/2']tpubD6NzVbkrYhZ4WQdzxL7NmJN7b85ePo4p6RSj9QQHF7te2RR9iUeVSGgnGkoUsB9LBRosgvNbjRv9bcsJgzgBd7QKuxDm23ZewkTRzNSLEDr",
+                "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+                "[12345678/48'/1'/0'/2']xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+            ]
+        );
+
+        // A key appearing several times is listed once
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwsh(or_d(pk(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/0/*),and_v(v:pk(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/*),older(144))))").unwrap();
+        let (template, keys) = desc.to_wallet_policy();
+        assert_eq!(
+            template,
+            "elwsh(or_d(pk(@0/0/*),and_v(v:pk(@0/1/*),older(144))))"
+        );
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn derived_script_pubkey() {
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let descriptor: Descriptor<DescriptorPublicKey> = "elsh(wsh(sortedmulti(1,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/0/*,xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/0/*)))".parse().unwrap();
+        for index in 0..10 {
+            let full = descriptor
+                .derive(index)
+                .translate_pk2(|xpk| xpk.derive_public_key(&secp))
+                .unwrap();
+            assert_eq!(
+                descriptor.derived_script_pubkey(&secp, index).unwrap(),
+                full.script_pubkey()
+            );
+        }
+
+        // Hardened wildcards cannot be derived without secret keys
+        let descriptor: Descriptor<DescriptorPublicKey> = "elwpkh(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/*')".parse().unwrap();
+        assert_eq!(
+            descriptor.derived_script_pubkey(&secp, 0),
+            Err(ConversionError::HardenedChild)
+        );
+    }
+
+    #[test]
+    fn derived_address() {
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let descriptor: Descriptor<DescriptorPublicKey> = "elwsh(sortedmulti(1,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/0/*,xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/0/*))".parse().unwrap();
+        // The checksummed form parses back to the same descriptor
+        let with_checksum = descriptor.to_string();
+        assert_eq!(
+            Descriptor::<DescriptorPublicKey>::from_str(&with_checksum).unwrap(),
+            descriptor
+        );
+        for index in 0..10 {
+            let address = descriptor
+                .derived_address(&secp, index, &elements::AddressParams::ELEMENTS)
+                .unwrap();
+            assert_eq!(
+                address.script_pubkey(),
+                descriptor.derived_script_pubkey(&secp, index).unwrap()
+            );
+            // Key order in a sortedmulti does not affect the address
+            let swapped: Descriptor<DescriptorPublicKey> = "elwsh(sortedmulti(1,xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/0/*,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/0/*))".parse().unwrap();
+            assert_eq!(
+                swapped
+                    .derived_address(&secp, index, &elements::AddressParams::ELEMENTS)
+                    .unwrap(),
+                address
+            );
+        }
+
+        // Regression vectors computed with this crate, not with Elements
+        // Core, to catch changes in derivation or key sorting
+        for &(index, expected) in &[
+            (
+                0,
+                "ert1qvjtfmrxu524qhdevl6yyyasjs7xmnzjlqlu60mrwepact60eyz9swyxeff",
+            ),
+            (
+                1,
+                "ert1qp6rfclasvmwys7w7j4svgc2mrujq9m73s5shpw4e799hwkdcqlcsehwz0l",
+            ),
+        ] {
+            assert_eq!(
+                descriptor
+                    .derived_address(&secp, index, &elements::AddressParams::ELEMENTS)
+                    .unwrap()
+                    .to_string(),
+                expected
+            );
+        }
+
+        // The same keys wrapped in sh(wsh) give the script hash of the
+        // bitcoin address `325zcVBN5o2eqqqtGwPjmtDd8dJRyYP82s` at index 5
+        let descriptor: Descriptor<DescriptorPublicKey> = "elsh(wsh(sortedmulti(1,xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/0/*,xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/0/*)))".parse().unwrap();
+        assert_eq!(
+            descriptor
+                .derived_address(&secp, 5, &elements::AddressParams::ELEMENTS)
+                .unwrap()
+                .to_string(),
+            "XBkDY63XnRTz6BbwzJi3ifGhBwLTomEzkq"
+        );
+        let btc_addr = bitcoin::Address::from_str("325zcVBN5o2eqqqtGwPjmtDd8dJRyYP82s").unwrap();
+        assert_eq!(
+            descriptor
+                .derived_script_pubkey(&secp, 5)
+                .unwrap()
+                .as_bytes(),
+            btc_addr.script_pubkey().as_bytes()
+        );
+
+        let descriptor: Descriptor<DescriptorPublicKey> = "elwpkh(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/*')".parse().unwrap();
+        assert!(descriptor
+            .derived_address(&secp, 0, &elements::AddressParams::ELEMENTS)
+            .is_err());
+    }
+
+    #[test]
+    fn same_spk_as() {
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let a = "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/0/*";
+        let b = "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH/0/0/*";
+        let desc = |s: String| Descriptor::<DescriptorPublicKey>::from_str(&s).unwrap();
+
+        let sorted_ab = desc(format!("elwsh(sortedmulti(2,{},{}))", a, b));
+        let sorted_ba = desc(format!("elwsh(sortedmulti(2,{},{}))", b, a));
+        assert!(sorted_ab.same_spk_as(&sorted_ba, &secp, 0..20).unwrap());
+
+        let multi_ab = desc(format!("elwsh(multi(2,{},{}))", a, b));
+        let multi_ba = desc(format!("elwsh(multi(2,{},{}))", b, a));
+        assert!(!multi_ab.same_spk_as(&multi_ba, &secp, 0..20).unwrap());
+        assert!(multi_ab.same_spk_as(&multi_ab, &secp, 0..20).unwrap());
+    }
+
+    #[test]
+    fn find_derivation_index() {
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let desc = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/*)").unwrap();
+        let spk = desc.derived_script_pubkey(&secp, 5).unwrap();
+        assert_eq!(
+            desc.find_derivation_index(&secp, &spk, 0..100).unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            desc.find_derivation_index(&secp, &spk, 6..100).unwrap(),
+            None
+        );
+
+        let hardened = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/1/*')").unwrap();
+        assert!(hardened.find_derivation_index(&secp, &spk, 0..100).is_err());
+    }
+
+    #[test]
+    fn to_bitcoin_descriptor() {
+        use BtcDescriptorTrait;
+
+        let desc = StdDescriptor::from_str(
+            "elwsh(multi(2,\
+             0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798,\
+             02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5,\
+             03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))",
+        )
+        .unwrap();
+        let btc_desc = desc.to_bitcoin_descriptor().unwrap();
+        assert_eq!(
+            btc_desc.to_string().split('#').next().unwrap(),
+            &desc.to_string().split('#').next().unwrap()[2..]
+        );
+        assert_eq!(
+            btc_desc.script_pubkey().as_bytes(),
+            desc.script_pubkey().as_bytes()
+        );
+
+        for nested in &[
+            "elsh(wpkh(03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))",
+            "elsh(wsh(and_v(v:pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),\
+             older(144))))",
+        ] {
+            let desc = StdDescriptor::from_str(nested).unwrap();
+            let btc_desc = desc.to_bitcoin_descriptor().unwrap();
+            assert_eq!(
+                btc_desc.to_string().split('#').next().unwrap(),
+                &nested[2..]
+            );
+            assert_eq!(
+                btc_desc.script_pubkey().as_bytes(),
+                desc.script_pubkey().as_bytes()
+            );
+        }
+
+        let cov = StdDescriptor::from_str(&format!(
+            "elcovwsh({},pk({}))",
+            "03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7",
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        ))
+        .unwrap();
+        assert!(cov.to_bitcoin_descriptor().is_err());
+    }
+
+    #[test]
+    fn pegin_addresses() {
+        use bitcoin::util::address::Payload;
+        use descriptor::pegin::{Pegin, PeginTrait};
+        use BtcDescriptor;
+
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let fed_desc = BtcDescriptor::<PublicKey>::from_str(
+            "wsh(multi(2,\
+             0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798,\
+             02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5))",
+        )
+        .unwrap();
+        let claim_desc = StdDescriptor::from_str(
+            "elwpkh(03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7)",
+        )
+        .unwrap();
+        let pegin = Pegin::new(fed_desc, claim_desc.clone());
+
+        let addrs = pegin
+            .addresses(
+                bitcoin::Network::Bitcoin,
+                &elements::AddressParams::ELEMENTS,
+                &secp,
+            )
+            .unwrap();
+
+        // The mainchain watch address is the tweaked federation script,
+        // wrapped in sh(wsh)
+        match addrs.bitcoin_address.payload {
+            Payload::ScriptHash(_) => {}
+            ref p => panic!("unexpected mainchain payload {:?}", p),
+        }
+        assert_eq!(
+            addrs.bitcoin_address.script_pubkey(),
+            pegin.bitcoin_script_pubkey(&secp)
+        );
+        assert_eq!(
+            addrs.bitcoin_address.script_pubkey(),
+            pegin.bitcoin_witness_script(&secp).to_v0_p2wsh().to_p2sh()
+        );
+        // The sidechain claim address is that of the claim script
+        assert_eq!(
+            addrs.elements_address.script_pubkey(),
+            claim_desc.script_pubkey()
+        );
+        assert_eq!(
+            addrs.elements_address,
+            claim_desc
+                .address(&elements::AddressParams::ELEMENTS)
+                .unwrap()
+        );
+    }
+}
+
+#[cfg(all(test, feature = "unstable"))]
+mod benches {
+    use std::str::FromStr;
+    use test::{black_box, Bencher};
+
+    use elements::secp256k1_zkp;
+    use {Descriptor, DescriptorPublicKey};
+
+    #[bench]
+    pub fn derive_10k_script_pubkeys(bh: &mut Bencher) {
+        let secp = secp256k1_zkp::Secp256k1::verification_only();
+        let descriptor = Descriptor::<DescriptorPublicKey>::from_str("elwpkh(xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB/0/*)").expect("parsing");
+        bh.iter(|| {
+            for index in 0..10_000 {
+                black_box(descriptor.derived_script_pubkey(&secp, index)).unwrap();
+            }
+        });
+    }
 }
@@ -26,7 +26,7 @@ use bitcoin::{self, blockdata::script, hashes};
 #[allow(deprecated)]
 use bitcoin::{blockdata::opcodes, util::contracthash};
 use bitcoin::{hashes::hash160, Address as BtcAddress};
-use elements::secp256k1_zkp;
+use elements::{self, secp256k1_zkp};
 use expression::{self, FromTree};
 use policy::{semantic, Liftable};
 use std::{
@@ -50,7 +50,7 @@ use {tweak_key, util::varint_len};
 
 use descriptor::checksum::{desc_checksum, verify_checksum};
 
-use super::PeginTrait;
+use super::{PeginAddresses, PeginTrait};
 use {MiniscriptKey, ToPublicKey};
 
 /// New Pegin Descriptor with Miniscript support
@@ -73,6 +73,29 @@ impl<Pk: MiniscriptKey> Pegin<Pk> {
     }
 }
 
+impl<Pk: MiniscriptKey + ToPublicKey> Pegin<Pk>
+where
+    Pk: FromStr,
+    Pk::Hash: FromStr,
+    <Pk as FromStr>::Err: ToString,
+    <<Pk as MiniscriptKey>::Hash as FromStr>::Err: ToString,
+{
+    /// Computes both the mainchain address watched for the peg-in deposit
+    /// and the sidechain address of the claim script.
+    /// Requires the secp context to compute the tweak
+    pub fn addresses<C: secp256k1_zkp::Verification>(
+        &self,
+        network: bitcoin::Network,
+        params: &'static elements::AddressParams,
+        secp: &secp256k1_zkp::Secp256k1<C>,
+    ) -> Result<PeginAddresses, Error> {
+        Ok(PeginAddresses {
+            bitcoin_address: self.bitcoin_address(network, secp)?,
+            elements_address: self.elem_desc.address(params)?,
+        })
+    }
+}
+
 impl<Pk: MiniscriptKey> fmt::Debug for Pegin<Pk> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "pegin({:?},{:?})", self.fed_desc, self.elem_desc)
@@ -27,7 +27,7 @@ use bitcoin::hashes::Hash;
 use bitcoin::Script as BtcScript;
 use bitcoin::{self, blockdata::script, hashes};
 use bitcoin::{hashes::hash160, Address as BtcAddress};
-use elements::secp256k1_zkp;
+use elements::{self, secp256k1_zkp};
 use expression::{self, FromTree};
 use policy::{semantic, Liftable};
 use std::{
@@ -53,7 +53,20 @@ use {MiniscriptKey, ToPublicKey};
 
 mod dynafed_pegin;
 mod legacy_pegin;
+pub use self::dynafed_pegin::Pegin;
 pub use self::legacy_pegin::{LegacyPegin, LegacyPeginKey};
+
+/// The pair of addresses involved in a single peg-in: the mainchain
+/// address that the federation watches for the deposit, and the
+/// sidechain address committed to by the claim script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeginAddresses {
+    /// Mainchain address to which the peg-in deposit is sent
+    pub bitcoin_address: bitcoin::Address,
+    /// Sidechain address at which the pegged-in funds are claimed
+    pub elements_address: elements::Address,
+}
+
 /// A general trait for Pegin Bitcoin descriptor.
 /// It should also support FromStr, fmt::Display and should be liftable
 /// to bitcoin Semantic Policy.
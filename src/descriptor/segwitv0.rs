@@ -33,6 +33,7 @@ use {
 use NoExt;
 
 use super::{
+    check_nesting,
     checksum::{desc_checksum, verify_checksum},
     DescriptorTrait, ElementsTrait, SortedMultiVec, ELMTS_STR,
 };
@@ -90,6 +91,7 @@ impl<Pk: MiniscriptKey> Wsh<Pk> {
     {
         if top.name == "wsh" && top.args.len() == 1 {
             let top = &top.args[0];
+            check_nesting("elsh(wsh)", top.name, &[])?;
             if top.name == "sortedmulti" {
                 return Ok(Wsh {
                     inner: WshInner::SortedMulti(SortedMultiVec::from_tree(&top)?),
@@ -140,6 +142,7 @@ where
     fn from_tree(top: &expression::Tree) -> Result<Self, Error> {
         if top.name == "elwsh" && top.args.len() == 1 {
             let top = &top.args[0];
+            check_nesting("elwsh", top.name, &[])?;
             if top.name == "sortedmulti" {
                 return Ok(Wsh {
                     inner: WshInner::SortedMulti(SortedMultiVec::from_tree(&top)?),
@@ -20,11 +20,14 @@
 
 use std::{fmt, str::FromStr};
 
+use elements::hashes::{sha256, Hash};
 use elements::secp256k1_zkp;
 use elements::{self, script, Script};
 
 use expression::{self, FromTree};
+use interpreter::Interpreter;
 use miniscript::context::ScriptContext;
+use miniscript::limits::{SEQUENCE_LOCKTIME_MASK, SEQUENCE_LOCKTIME_TYPE_FLAG};
 use policy::{semantic, Liftable};
 use push_opcode_size;
 use util::{varint_len, witness_to_scriptsig};
@@ -36,6 +39,7 @@ use {
 use NoExt;
 
 use super::{
+    check_nesting,
     checksum::{desc_checksum, verify_checksum},
     DescriptorTrait, ElementsTrait, SortedMultiVec, Wpkh, Wsh, ELMTS_STR,
 };
@@ -85,12 +89,7 @@ impl<Pk: MiniscriptKey> fmt::Debug for Sh<Pk> {
 
 impl<Pk: MiniscriptKey> fmt::Display for Sh<Pk> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let desc = match self.inner {
-            ShInner::Wsh(ref wsh) => format!("{}sh({})", ELMTS_STR, wsh.to_string_no_checksum()),
-            ShInner::Wpkh(ref pk) => format!("{}sh({})", ELMTS_STR, pk.to_string_no_checksum()),
-            ShInner::SortedMulti(ref smv) => format!("{}sh({})", ELMTS_STR, smv),
-            ShInner::Ms(ref ms) => format!("{}sh({})", ELMTS_STR, ms),
-        };
+        let desc = self.to_string_no_checksum();
         let checksum = desc_checksum(&desc).map_err(|_| fmt::Error)?;
         write!(f, "{}#{}", &desc, &checksum)
     }
@@ -106,6 +105,7 @@ where
     fn from_tree(top: &expression::Tree) -> Result<Self, Error> {
         if top.name == "elsh" && top.args.len() == 1 {
             let top = &top.args[0];
+            check_nesting("elsh", top.name, &["wsh", "wpkh"])?;
             let inner = match top.name {
                 "wsh" => ShInner::Wsh(Wsh::from_inner_tree(&top)?),
                 "wpkh" => ShInner::Wpkh(Wpkh::from_inner_tree(&top)?),
@@ -195,6 +195,73 @@ impl<Pk: MiniscriptKey> Sh<Pk> {
             inner: ShInner::Wpkh(Wpkh::new(pk)?),
         })
     }
+
+    // The `Display` form without the checksum
+    pub(crate) fn to_string_no_checksum(&self) -> String {
+        match self.inner {
+            ShInner::Wsh(ref wsh) => format!("{}sh({})", ELMTS_STR, wsh.to_string_no_checksum()),
+            ShInner::Wpkh(ref pk) => format!("{}sh({})", ELMTS_STR, pk.to_string_no_checksum()),
+            ShInner::SortedMulti(ref smv) => format!("{}sh({})", ELMTS_STR, smv),
+            ShInner::Ms(ref ms) => format!("{}sh({})", ELMTS_STR, ms),
+        }
+    }
+}
+
+impl<Pk> Sh<Pk>
+where
+    Pk: MiniscriptKey + ToPublicKey + FromStr,
+    Pk::Hash: FromStr,
+    <Pk as FromStr>::Err: ToString,
+    <<Pk as MiniscriptKey>::Hash as FromStr>::Err: ToString,
+{
+    /// Look up a complete scriptSig for a legacy (non-segwit) spend from
+    /// the satisfier, keyed by the descriptor id, and check that it
+    /// satisfies the descriptor: its last push must be the redeem script
+    /// and the interpreter must run it to completion.
+    ///
+    /// Signatures can only be verified against the spending transaction, so
+    /// they are only checked to be present and well formed. For the same
+    /// reason every absolute timelock is taken to be met, as is either every
+    /// height-based or every time-based relative timelock.
+    fn lookup_complete_script_sig<S>(&self, satisfier: &S) -> Result<Option<Script>, Error>
+    where
+        S: Satisfier<Pk>,
+    {
+        // Same as `Descriptor::id`, without wrapping `self` in a descriptor
+        let id = sha256::Hash::hash(self.to_string_no_checksum().as_bytes()).into_inner();
+        let script_sig = match satisfier.lookup_complete_scriptsig(&id) {
+            Some(script_sig) => script_sig,
+            None => return Ok(None),
+        };
+        let invalid = |reason: String| {
+            Error::Unexpected(format!(
+                "provided scriptSig does not satisfy the descriptor: {}",
+                reason
+            ))
+        };
+
+        let redeem_script = self.explicit_script();
+        match script_sig.instructions().last() {
+            Some(Ok(script::Instruction::PushBytes(push))) if push == redeem_script.as_bytes() => {}
+            _ => return Err(invalid(String::from("last push is not the redeem script"))),
+        }
+
+        let spk = self.script_pubkey();
+        let mut err = None;
+        for &sequence in &[
+            SEQUENCE_LOCKTIME_MASK,
+            SEQUENCE_LOCKTIME_TYPE_FLAG | SEQUENCE_LOCKTIME_MASK,
+        ] {
+            let mut interpreter =
+                Interpreter::from_txdata(&spk, &script_sig, &[], u32::max_value(), sequence)
+                    .map_err(|e| invalid(e.to_string()))?;
+            match interpreter.iter(|_, _| true).filter_map(Result::err).next() {
+                None => return Ok(Some(script_sig)),
+                Some(e) => err = Some(e),
+            }
+        }
+        Err(invalid(err.expect("interpreter ran").to_string()))
+    }
 }
 
 impl<Pk: MiniscriptKey> ElementsTrait<Pk> for Sh<Pk>
@@ -316,6 +383,14 @@ where
         Pk: ToPublicKey,
         S: Satisfier<Pk>,
     {
+        match self.inner {
+            ShInner::SortedMulti(..) | ShInner::Ms(..) => {
+                if let Some(script_sig) = self.lookup_complete_script_sig(&satisfier)? {
+                    return Ok((vec![], script_sig));
+                }
+            }
+            ShInner::Wsh(..) | ShInner::Wpkh(..) => {}
+        }
         let script_sig = self.unsigned_script_sig();
         match self.inner {
             ShInner::Wsh(ref wsh) => {
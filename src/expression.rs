@@ -47,12 +47,17 @@ impl<'a> fmt::Display for Tree<'a> {
     }
 }
 impl<'a> Tree<'a> {
-    fn from_slice(sl: &'a str) -> Result<(Tree<'a>, &'a str), Error> {
-        Self::from_slice_helper(sl, 0u32)
+    fn from_slice(sl: &'a str, max_depth: u32) -> Result<(Tree<'a>, &'a str), Error> {
+        Self::from_slice_helper(sl, 0u32, max_depth, sl)
     }
 
-    fn from_slice_helper(mut sl: &'a str, depth: u32) -> Result<(Tree<'a>, &'a str), Error> {
-        if depth >= MAX_RECURSION_DEPTH {
+    fn from_slice_helper(
+        mut sl: &'a str,
+        depth: u32,
+        max_depth: u32,
+        full: &'a str,
+    ) -> Result<(Tree<'a>, &'a str), Error> {
+        if depth >= max_depth {
             return Err(Error::MaxRecursiveDepthExceeded);
         }
         enum Found {
@@ -132,18 +137,18 @@ impl<'a> Tree<'a> {
 
                 sl = &sl[n + 1..];
                 loop {
-                    let (arg, new_sl) = Tree::from_slice_helper(sl, depth + 1)?;
+                    let (arg, new_sl) = Tree::from_slice_helper(sl, depth + 1, max_depth, full)?;
                     ret.args.push(arg);
 
                     if new_sl.is_empty() {
-                        return Err(Error::ExpectedChar(')'));
+                        return Err(parse_err(full, new_sl, Error::ExpectedChar(')')));
                     }
 
                     sl = &new_sl[1..];
                     match new_sl.as_bytes()[0] {
                         b',' => {}
                         b')' => break,
-                        _ => return Err(Error::ExpectedChar(',')),
+                        _ => return Err(parse_err(full, new_sl, Error::ExpectedChar(','))),
                     }
                 }
                 Ok((ret, sl))
@@ -153,6 +158,14 @@ impl<'a> Tree<'a> {
 
     /// Parses a tree from a string
     pub fn from_str(s: &'a str) -> Result<Tree<'a>, Error> {
+        Tree::from_str_with_max_depth(s, MAX_RECURSION_DEPTH)
+    }
+
+    /// Parses a tree from a string, failing with
+    /// [`Error::MaxRecursiveDepthExceeded`] if expressions are nested more
+    /// than `max_depth` levels deep. Services parsing untrusted input may
+    /// want a tighter bound than the default used by [`Tree::from_str`].
+    pub fn from_str_with_max_depth(s: &'a str, max_depth: u32) -> Result<Tree<'a>, Error> {
         // Filter out non-ASCII because we byte-index strings all over the
         // place and Rust gets very upset when you splinch a string.
         for ch in s.bytes() {
@@ -161,15 +174,25 @@ impl<'a> Tree<'a> {
             }
         }
 
-        let (top, rem) = Tree::from_slice(s)?;
+        let (top, rem) = Tree::from_slice(s, max_depth)?;
         if rem.is_empty() {
             Ok(top)
         } else {
-            Err(errstr(rem))
+            Err(parse_err(s, rem, errstr(rem)))
         }
     }
 }
 
+/// Attach the position of the unparsed remainder `rem` of `s`
+/// (and a short snippet of it) to a parse error
+fn parse_err(s: &str, rem: &str, err: Error) -> Error {
+    Error::ParseTree {
+        pos: s.len() - rem.len(),
+        context: rem.chars().take(16).collect(),
+        err: Box::new(err),
+    }
+}
+
 /// Parse a string as a u32, for timelocks or thresholds
 pub fn parse_num(s: &str) -> Result<u32, Error> {
     if s.len() > 1 {
@@ -229,7 +252,8 @@ where
 #[cfg(test)]
 mod tests {
 
-    use super::parse_num;
+    use super::{parse_num, Tree};
+    use Error;
 
     #[test]
     fn test_parse_num() {
@@ -240,4 +264,45 @@ mod tests {
         assert!(parse_num("+6").is_err());
         assert!(parse_num("-6").is_err());
     }
+
+    #[test]
+    fn parse_error_position() {
+        // Unbalanced closing paren
+        match Tree::from_str("elwsh(pk(A)))") {
+            Err(Error::ParseTree { pos, context, .. }) => {
+                assert_eq!(pos, 12);
+                assert_eq!(context, ")");
+            }
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        // Missing closing paren
+        match Tree::from_str("elwsh(or_d(pk(A),pk(B))") {
+            Err(Error::ParseTree { pos, err, .. }) => {
+                assert_eq!(pos, 23);
+                match *err {
+                    Error::ExpectedChar(')') => {}
+                    x => panic!("unexpected error {:?}", x),
+                }
+            }
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn max_depth() {
+        let deep = format!("{}0{}", "or_i(0,".repeat(10000), ")".repeat(10000));
+        match Tree::from_str(&deep) {
+            Err(Error::MaxRecursiveDepthExceeded) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        let shallow = "or_i(0,or_i(0,or_i(0,0)))";
+        assert!(Tree::from_str(shallow).is_ok());
+        assert!(Tree::from_str_with_max_depth(shallow, 4).is_ok());
+        match Tree::from_str_with_max_depth(shallow, 3) {
+            Err(Error::MaxRecursiveDepthExceeded) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
 }
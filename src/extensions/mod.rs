@@ -23,7 +23,7 @@ use miniscript::{
 use interpreter::{self, Stack};
 mod outputs_pref;
 mod tx_ver;
-pub use self::outputs_pref::OutputsPref;
+pub use self::outputs_pref::{OutputConstraint, OutputsPref};
 pub use self::tx_ver::VerEq;
 
 /// Extensions to elements-miniscript.
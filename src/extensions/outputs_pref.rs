@@ -2,15 +2,16 @@
 //! Note that this fragment is only supported for Segwit context
 //! You are most likely looking for taproot direct tx introspection
 
-use std::fmt;
+use std::{fmt, io};
 
 use MiniscriptKey;
 
+use elements::encode::Decodable;
 use elements::hashes::hex::FromHex;
 use elements::hashes::hex::ToHex;
 use elements::hashes::sha256d;
 use elements::hashes::Hash;
-use elements::{self, encode::serialize};
+use elements::{self, confidential, encode::serialize, AssetId};
 use Extension;
 use ForEach;
 
@@ -52,6 +53,76 @@ pub struct OutputsPref {
     pub pref: Vec<u8>,
 }
 
+/// The fields of a single output of the spending transaction which are
+/// pinned by a covenant. Fields which are not committed to are `None`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct OutputConstraint {
+    /// Index of the output in the spending transaction
+    pub index: usize,
+    /// The asset the output must have
+    pub asset: Option<confidential::Asset>,
+    /// The value the output must have
+    pub value: Option<confidential::Value>,
+    /// The nonce the output must have
+    pub nonce: Option<confidential::Nonce>,
+    /// The scriptPubKey the output must have
+    pub script_pubkey: Option<elements::Script>,
+}
+
+impl OutputsPref {
+    /// The constraints the prefix puts on the outputs of the spending
+    /// transaction, one entry per output it commits to
+    ///
+    /// The prefix is decoded as a sequence of serialized outputs. Decoding
+    /// stops at the first field which is not fully contained in the prefix,
+    /// so a partially committed output only has its leading fields pinned.
+    pub fn output_constraints(&self) -> Vec<OutputConstraint> {
+        let mut ret = vec![];
+        let mut cursor = io::Cursor::new(&self.pref[..]);
+        for index in 0.. {
+            let mut constraint = OutputConstraint {
+                index: index,
+                asset: None,
+                value: None,
+                nonce: None,
+                script_pubkey: None,
+            };
+            constraint.asset = confidential::Asset::consensus_decode(&mut cursor).ok();
+            if constraint.asset.is_some() {
+                constraint.value = confidential::Value::consensus_decode(&mut cursor).ok();
+            }
+            if constraint.value.is_some() {
+                constraint.nonce = confidential::Nonce::consensus_decode(&mut cursor).ok();
+            }
+            if constraint.nonce.is_some() {
+                constraint.script_pubkey = elements::Script::consensus_decode(&mut cursor).ok();
+            }
+            let complete = constraint.script_pubkey.is_some();
+            if constraint.asset.is_some() {
+                ret.push(constraint);
+            }
+            if !complete {
+                break;
+            }
+        }
+        ret
+    }
+
+    /// The explicit assets of the outputs committed to by the prefix
+    ///
+    /// The asset of a partially committed output is still reported,
+    /// see [`OutputsPref::output_constraints`].
+    pub fn referenced_assets(&self) -> Vec<AssetId> {
+        self.output_constraints()
+            .into_iter()
+            .filter_map(|constraint| match constraint.asset {
+                Some(confidential::Asset::Explicit(asset)) => Some(asset),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 impl fmt::Display for OutputsPref {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "outputs_pref({})", self.pref.to_hex())
@@ -279,7 +350,10 @@ impl<Pk: MiniscriptKey> Extension<Pk> for OutputsPref {
             let len = stack.len();
             // Add the max_elems suffix elements
             for i in 0..max_elems {
-                outputs_builder.extend(stack[len - max_elems + i].into_slice());
+                match stack[len - max_elems + i].into_slice() {
+                    Ok(sl) => outputs_builder.extend(sl),
+                    Err(e) => return Some(Err(e)),
+                }
             }
             // Pop the max_elems suffix elements
             for _ in 0..max_elems {
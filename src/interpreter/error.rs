@@ -26,6 +26,8 @@ pub enum Error {
     CouldNotEvaluate,
     /// We expected a push (including a `OP_1` but no other numeric pushes)
     ExpectedPush,
+    /// We expected a push encoding a script number
+    ExpectedNum,
     /// The preimage to the hash function must be exactly 32 bytes.
     HashPreimageLengthMismatch,
     /// Incorrect scriptPubKey (pay-to-pubkeyhash) for the provided public key
@@ -49,6 +51,12 @@ pub enum Error {
     /// this error. This is network standardness assumption and miniscript only
     /// supports standard scripts
     MultiSigEvaluationError,
+    /// The extra dummy element consumed by `OP_CHECKMULTISIG` must be empty
+    /// to satisfy relay policy (`NULLDUMMY`)
+    NonEmptyDummy,
+    /// The result of an arithmetic operation was used as an operand
+    /// but does not fit in a 4-byte script number
+    NumOverflow(i64),
     ///Witness must be empty for pre-segwit transactions
     NonEmptyWitness,
     ///ScriptSig must be empty for pure segwit transactions
@@ -68,6 +76,13 @@ pub enum Error {
     RelativeLocktimeNotMet(u32),
     /// Forward-secp related errors
     Secp(secp256k1_zkp::Error),
+    /// Substring operation (e.g. `OP_LEFT`) out of the bounds of the element
+    SubstrOutOfRange {
+        /// The requested length
+        n: i64,
+        /// The length of the element
+        len: usize,
+    },
     /// Miniscript requires the entire top level script to be satisfied.
     ScriptSatisfactionError,
     /// An uncompressed public key was encountered in a context where it is
@@ -100,6 +115,64 @@ pub enum Error {
     },
 }
 
+/// Whether an interpreter error only rules out the branch being evaluated,
+/// or means the witness is malformed and cannot satisfy the script at all
+///
+/// Note that a well-formed witness which simply does not satisfy a fragment
+/// (e.g. a 32-byte preimage with the wrong hash, or an empty signature) is
+/// not an error: the `Stack` evaluators report it by returning `None` and
+/// pushing a dissatisfaction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    /// The witness is well-formed but the branch cannot be taken in the
+    /// spending context, e.g. a timelock which is not met yet
+    SoftFail,
+    /// The witness is malformed, e.g. an unparseable signature or a
+    /// preimage of the wrong size, and would fail script execution
+    HardFail,
+}
+
+impl Error {
+    /// Classifies this error as a soft or hard failure, see [`FailureKind`]
+    pub fn failure_kind(&self) -> FailureKind {
+        match *self {
+            Error::AbsoluteLocktimeNotMet(..) | Error::RelativeLocktimeNotMet(..) => {
+                FailureKind::SoftFail
+            }
+            Error::CouldNotEvaluate
+            | Error::ExpectedPush
+            | Error::ExpectedNum
+            | Error::HashPreimageLengthMismatch
+            | Error::IncorrectPubkeyHash
+            | Error::IncorrectScriptHash
+            | Error::IncorrectWPubkeyHash
+            | Error::IncorrectWScriptHash
+            | Error::InsufficientSignaturesMultiSig
+            | Error::InvalidSignature(..)
+            | Error::Miniscript(..)
+            | Error::MissingExtraZeroMultiSig
+            | Error::MultiSigEvaluationError
+            | Error::NonEmptyDummy
+            | Error::NumOverflow(..)
+            | Error::NonEmptyWitness
+            | Error::NonEmptyScriptSig
+            | Error::PkEvaluationError(..)
+            | Error::PkHashVerifyFail(..)
+            | Error::PubkeyParseError
+            | Error::Secp(..)
+            | Error::SubstrOutOfRange { .. }
+            | Error::ScriptSatisfactionError
+            | Error::UncompressedPubkey
+            | Error::UnexpectedStackBoolean
+            | Error::UnexpectedStackEnd
+            | Error::UnexpectedStackElementPush
+            | Error::VerifyFailed
+            | Error::IncorrectCovenantWitness
+            | Error::CovWitnessSizeErr { .. } => FailureKind::HardFail,
+        }
+    }
+}
+
 #[doc(hidden)]
 impl From<secp256k1_zkp::Error> for Error {
     fn from(e: secp256k1_zkp::Error) -> Error {
@@ -139,6 +212,7 @@ impl fmt::Display for Error {
                 n
             ),
             Error::ExpectedPush => f.write_str("expected push in script"),
+            Error::ExpectedNum => f.write_str("expected script number push"),
             Error::CouldNotEvaluate => f.write_str("Interpreter Error: Could not evaluate"),
             Error::HashPreimageLengthMismatch => f.write_str("Hash preimage should be 32 bytes"),
             Error::IncorrectPubkeyHash => f.write_str("public key did not match scriptpubkey"),
@@ -149,6 +223,8 @@ impl fmt::Display for Error {
             Error::IncorrectWScriptHash => f.write_str("witness script did not match scriptpubkey"),
             Error::InsufficientSignaturesMultiSig => f.write_str("Insufficient signatures for CMS"),
             Error::InvalidSignature(pk) => write!(f, "bad signature with pk {}", pk),
+            Error::NonEmptyDummy => f.write_str("CMS dummy element is not empty"),
+            Error::NumOverflow(n) => write!(f, "script number {} exceeds 4 bytes", n),
             Error::NonEmptyWitness => f.write_str("legacy spend had nonempty witness"),
             Error::NonEmptyScriptSig => f.write_str("segwit spend had nonempty scriptsig"),
             Error::Miniscript(ref e) => write!(f, "parse error: {}", e),
@@ -164,6 +240,11 @@ impl fmt::Display for Error {
             }
             Error::ScriptSatisfactionError => f.write_str("Top level script must be satisfied"),
             Error::Secp(ref e) => fmt::Display::fmt(e, f),
+            Error::SubstrOutOfRange { n, len } => write!(
+                f,
+                "substring of length {} out of range for element of length {}",
+                n, len
+            ),
             Error::UncompressedPubkey => {
                 f.write_str("uncompressed pubkey in non-legacy descriptor")
             }
@@ -60,6 +60,7 @@ fn script_from_stackelem<'a, Ext: Extension<bitcoin::PublicKey>>(
         stack::Element::Dissatisfied => {
             Miniscript::from_ast(::Terminal::False).map_err(Error::from)
         }
+        stack::Element::Num(_) => Err(Error::ExpectedPush),
     }
 }
 
@@ -127,6 +128,11 @@ pub fn from_txdata<'txin, Ext: Extension<bitcoin::PublicKey>>(
     script_sig: &'txin elements::Script,
     witness: &'txin [Vec<u8>],
 ) -> Result<(Inner<Ext>, Stack<'txin>, elements::Script), Error> {
+    // Native segwit spends must have an empty scriptSig. Check this before
+    // parsing it as pushes, so that non-push data is reported as such.
+    if (spk.is_v0_p2wpkh() || spk.is_v0_p2wsh()) && !script_sig.is_empty() {
+        return Err(Error::NonEmptyScriptSig);
+    }
     let mut ssig_stack: Stack = script_sig
         .instructions_minimal()
         .map(stack::Element::from_instruction)
@@ -324,8 +330,8 @@ mod tests {
     use super::*;
     use elements::hashes::hex::FromHex;
     use elements::hashes::{hash160, sha256, Hash};
-    use elements::script;
     use elements::{self, Script};
+    use elements::{opcodes, script};
     use std::str::FromStr;
     use AllExt;
 
@@ -717,6 +723,12 @@ mod tests {
             .into_script();
         let err = from_txdata::<AllExt>(&spk, &script_sig, &wit_stack).unwrap_err();
         assert_eq!(&err.to_string(), "segwit spend had nonempty scriptsig");
+        // non-push script_sig
+        let script_sig = script::Builder::new()
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script();
+        let err = from_txdata::<AllExt>(&spk, &script_sig, &wit_stack).unwrap_err();
+        assert_eq!(&err.to_string(), "segwit spend had nonempty scriptsig");
     }
 
     #[test]
@@ -755,6 +767,20 @@ mod tests {
         let err = from_txdata::<AllExt>(&spk, &redeem_script, &wit_stack).unwrap_err();
         assert_eq!(&err.to_string(), "redeem script did not match scriptpubkey");
 
+        // with data in the scriptsig besides the redeem script
+        let extra_script_sig = script::Builder::new()
+            .push_int(1)
+            .push_slice(&redeem_script[..])
+            .into_script();
+        let err = from_txdata::<AllExt>(&spk, &extra_script_sig, &wit_stack).unwrap_err();
+        assert_eq!(&err.to_string(), "segwit spend had nonempty scriptsig");
+        let extra_script_sig = script::Builder::new()
+            .push_slice(&redeem_script[..])
+            .push_opcode(opcodes::all::OP_DROP)
+            .into_script();
+        let err = from_txdata::<AllExt>(&spk, &extra_script_sig, &wit_stack).unwrap_err();
+        assert_eq!(&err.to_string(), "expected push in script");
+
         // with correct witness
         let (inner, stack, script_code) =
             from_txdata::<AllExt>(&spk, &script_sig, &wit_stack).expect("parse txdata");
@@ -20,6 +20,7 @@
 //!
 
 use bitcoin::PublicKey;
+use descriptor::{HashLock, SighashContext};
 use elements::{self, secp256k1_zkp, SigHash};
 use elements::{confidential, sighash};
 use elements::{
@@ -28,6 +29,7 @@ use elements::{
 };
 use miniscript::context::NoChecks;
 use miniscript::ScriptContext;
+use std::fmt;
 use util;
 use Miniscript;
 use Terminal;
@@ -39,7 +41,7 @@ mod stack;
 
 use {AllExt, Extension};
 
-pub use self::error::Error;
+pub use self::error::{Error, FailureKind};
 pub use self::stack::{Element, Stack};
 
 /// An iterable Miniscript-structured representation of the spending of a coin
@@ -47,8 +49,8 @@ pub struct Interpreter<'txin, Ext: Extension<PublicKey>> {
     inner: inner::Inner<Ext>,
     stack: Stack<'txin>,
     script_code: elements::Script,
-    age: u32,
-    height: u32,
+    lock_time: u32,
+    sequence: u32,
 }
 
 impl<'txin> Interpreter<'txin, AllExt> {
@@ -59,14 +61,20 @@ impl<'txin> Interpreter<'txin, AllExt> {
     /// function; otherwise, it should be a closure containing a sighash and
     /// secp context, which can actually verify a given signature.
     /// For downstream cursom implementations of [`Extension`], use [`Interpreter::from_txdata_ext`]
+    ///
+    /// `lock_time` is the `nLockTime` of the spending transaction, checked by
+    /// `after` fragments, and `sequence` is the raw BIP68 `nSequence` of the
+    /// spending input, checked by `older` fragments. The disable flag and the
+    /// type flag of `sequence` are respected, so a plain block count must not
+    /// have either set.
     pub fn from_txdata(
         spk: &elements::Script,
         script_sig: &'txin elements::Script,
         witness: &'txin [Vec<u8>],
-        age: u32,
-        height: u32,
+        lock_time: u32,
+        sequence: u32,
     ) -> Result<Self, Error> {
-        Interpreter::from_txdata_ext(spk, script_sig, witness, age, height)
+        Interpreter::from_txdata_ext(spk, script_sig, witness, lock_time, sequence)
     }
 }
 
@@ -80,20 +88,23 @@ where
     /// that ECSDA signatures are valid, this can be set to the constant true
     /// function; otherwise, it should be a closure containing a sighash and
     /// secp context, which can actually verify a given signature.
+    ///
+    /// See [`Interpreter::from_txdata`] for the meaning of `lock_time` and
+    /// `sequence`.
     pub fn from_txdata_ext(
         spk: &elements::Script,
         script_sig: &'txin elements::Script,
         witness: &'txin [Vec<u8>],
-        age: u32,
-        height: u32,
+        lock_time: u32,
+        sequence: u32,
     ) -> Result<Self, Error> {
         let (inner, stack, script_code) = inner::from_txdata(spk, script_sig, witness)?;
         Ok(Interpreter {
             inner,
             stack,
             script_code,
-            age,
-            height,
+            lock_time,
+            sequence,
         })
     }
 
@@ -115,6 +126,7 @@ where
         Ext: Extension<PublicKey>,
         F: FnMut(&PublicKey, ElementsSig) -> bool,
     {
+        let initial_depth = self.stack.len();
         Iter {
             verify_sig: verify_sig,
             public_key: if let inner::Inner::PublicKey(ref pk, _) = self.inner {
@@ -136,15 +148,115 @@ where
                 inner::Inner::PublicKey(ref _pk, _) => vec![],
             },
             stack: &mut self.stack,
-            age: self.age,
-            height: self.height,
+            lock_time: self.lock_time,
+            sequence: self.sequence,
             cov: if let inner::Inner::CovScript(ref pk, ref _ms) = self.inner {
                 Some(pk)
             } else {
                 None
             },
             has_errored: false,
+            max_stack_depth: initial_depth,
+        }
+    }
+
+    /// Evaluates the spend and returns the maximum number of elements that
+    /// were on the stack at any point during its interpretation, including
+    /// the initial witness stack. Standardness rules limit this to
+    /// `MAX_STANDARD_P2WSH_STACK_ITEMS` for segwit spends.
+    ///
+    /// Like [`Interpreter::iter`], this consumes the internal stack and
+    /// returns an error if the spend is not satisfied.
+    pub fn max_stack_depth<F>(&'txin mut self, verify_sig: F) -> Result<usize, Error>
+    where
+        F: FnMut(&PublicKey, ElementsSig) -> bool,
+    {
+        let mut iter = self.iter(verify_sig);
+        while let Some(res) = iter.next() {
+            res?;
+        }
+        Ok(iter.max_stack_depth)
+    }
+
+    /// Evaluates the spend and summarizes the signatures, hash preimages
+    /// and timelocks which authorized it, e.g. for display by custody
+    /// software. Like [`Interpreter::iter`], this includes constraints
+    /// which did not contribute to the script being satisfied, consumes
+    /// the internal stack and returns an error if the spend is not
+    /// satisfied.
+    pub fn authorization_summary<F>(&'txin mut self, verify_sig: F) -> Result<AuthSummary, Error>
+    where
+        F: FnMut(&PublicKey, ElementsSig) -> bool,
+    {
+        let mut summary = AuthSummary::default();
+        for constraint in self.iter(verify_sig) {
+            match constraint? {
+                SatisfiedConstraint::PublicKey { key, .. } => summary.signers.push(*key),
+                SatisfiedConstraint::PublicKeyHash { key, .. } => summary.signers.push(key),
+                SatisfiedConstraint::HashLock { hash, preimage } => {
+                    let lock = match hash {
+                        HashLockType::Sha256(h) => HashLock::Sha256(*h),
+                        HashLockType::Hash256(h) => HashLock::Hash256(*h),
+                        HashLockType::Hash160(h) => HashLock::Hash160(*h),
+                        HashLockType::Ripemd160(h) => HashLock::Ripemd160(*h),
+                    };
+                    summary.preimages.push((lock, preimage.to_vec()));
+                }
+                SatisfiedConstraint::RelativeTimeLock { time } => {
+                    summary.relative_timelocks.push(*time)
+                }
+                SatisfiedConstraint::AbsoluteTimeLock { time } => {
+                    summary.absolute_timelocks.push(*time)
+                }
+                SatisfiedConstraint::VerEq { .. }
+                | SatisfiedConstraint::OutputsPref { .. }
+                | SatisfiedConstraint::Ext { .. } => {}
+            }
         }
+        Ok(summary)
+    }
+
+    /// Evaluates the spend and returns the distinct sighash types of all
+    /// signatures which passed `verify_sig`, in the order they were first
+    /// encountered. Anything other than `SigHashType::All` indicates that
+    /// some signature does not commit to every input and output of the
+    /// transaction.
+    ///
+    /// Like [`Interpreter::iter`], this consumes the internal stack and
+    /// returns an error if the spend is not satisfied.
+    pub fn sighash_types<F>(&'txin mut self, mut verify_sig: F) -> Result<Vec<SigHashType>, Error>
+    where
+        F: FnMut(&PublicKey, ElementsSig) -> bool,
+    {
+        let mut types = vec![];
+        {
+            let record_sig = |pk: &PublicKey, sig: ElementsSig| {
+                let valid = verify_sig(pk, sig);
+                if valid && !types.contains(&sig.1) {
+                    types.push(sig.1);
+                }
+                valid
+            };
+            for res in self.iter(record_sig) {
+                res?;
+            }
+        }
+        Ok(types)
+    }
+
+    /// Serializes the constraints satisfied by this spend as a JSON array
+    ///
+    /// Each [`SatisfiedConstraint`] is emitted as an object with a `type` field
+    /// and its keys, signatures, preimages and covenant data as hex strings.
+    /// Like [`Interpreter::iter`], this consumes the internal stack and returns
+    /// an error if the spend is not satisfied.
+    #[cfg(feature = "use-serde")]
+    pub fn constraints_json<F>(&'txin mut self, verify_sig: F) -> Result<String, Error>
+    where
+        F: FnMut(&PublicKey, ElementsSig) -> bool,
+    {
+        let constraints = self.iter(verify_sig).collect::<Result<Vec<_>, Error>>()?;
+        Ok(::serde_json::to_string(&constraints).expect("serializing constraints cannot fail"))
     }
 
     /// Outputs a "descriptor" string which reproduces the spent coins
@@ -192,6 +304,15 @@ where
         }
     }
 
+    /// The sighash algorithm the signatures of this spend commit to
+    pub fn sighash_context(&self) -> SighashContext {
+        if self.is_legacy() {
+            SighashContext::Legacy
+        } else {
+            SighashContext::SegwitV0
+        }
+    }
+
     /// Outputs a "descriptor" which reproduces the spent coins
     ///
     /// This may not represent the original descriptor used to produce the transaction,
@@ -216,10 +337,13 @@ where
         sighash_type: elements::SigHashType,
     ) -> secp256k1_zkp::Message {
         let mut sighash_cache = sighash::SigHashCache::new(unsigned_tx);
-        let hash = if self.is_legacy() {
-            sighash_cache.legacy_sighash(input_idx, &self.script_code, sighash_type)
-        } else {
-            sighash_cache.segwitv0_sighash(input_idx, &self.script_code, amount, sighash_type)
+        let hash = match self.sighash_context() {
+            SighashContext::Legacy => {
+                sighash_cache.legacy_sighash(input_idx, &self.script_code, sighash_type)
+            }
+            SighashContext::SegwitV0 => {
+                sighash_cache.segwitv0_sighash(input_idx, &self.script_code, amount, sighash_type)
+            }
         };
 
         secp256k1_zkp::Message::from_slice(&hash[..])
@@ -281,6 +405,49 @@ where
     }
 }
 
+/// Summary of the conditions which authorized a spend, as returned by
+/// [`Interpreter::authorization_summary`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuthSummary {
+    /// Keys which provided a valid signature
+    pub signers: Vec<PublicKey>,
+    /// Hash locks whose preimage was revealed, along with the preimage
+    pub preimages: Vec<(HashLock, Vec<u8>)>,
+    /// Satisfied relative timelocks (`older`)
+    pub relative_timelocks: Vec<u32>,
+    /// Satisfied absolute timelocks (`after`)
+    pub absolute_timelocks: Vec<u32>,
+}
+
+impl fmt::Display for AuthSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        if !self.signers.is_empty() {
+            let keys: Vec<_> = self.signers.iter().map(|pk| pk.to_string()).collect();
+            parts.push(format!("signed by [{}]", keys.join(",")));
+        }
+        for &(ref lock, _) in &self.preimages {
+            parts.push(match *lock {
+                HashLock::Sha256(ref h) => format!("revealed preimage for sha256 {}", h),
+                HashLock::Hash256(ref h) => format!("revealed preimage for hash256 {}", h),
+                HashLock::Ripemd160(ref h) => format!("revealed preimage for ripemd160 {}", h),
+                HashLock::Hash160(ref h) => format!("revealed preimage for hash160 {}", h),
+            });
+        }
+        for n in &self.relative_timelocks {
+            parts.push(format!("older({})", n));
+        }
+        for n in &self.absolute_timelocks {
+            parts.push(format!("after({})", n));
+        }
+        if parts.is_empty() {
+            f.write_str("no conditions")
+        } else {
+            f.write_str(&parts.join("; "))
+        }
+    }
+}
+
 /// Type of HashLock used for SatisfiedConstraint structure
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum HashLockType<'intp> {
@@ -354,6 +521,79 @@ pub enum SatisfiedConstraint<'intp, 'txin, Ext: 'intp + Extension<PublicKey>> {
     },
 }
 
+#[cfg(feature = "use-serde")]
+impl<'intp, 'txin, Ext> ::serde::Serialize for SatisfiedConstraint<'intp, 'txin, Ext>
+where
+    Ext: 'intp + Extension<PublicKey>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        use elements::hashes::hex::ToHex;
+        use serde::ser::SerializeStruct;
+
+        let mut st;
+        match *self {
+            SatisfiedConstraint::PublicKey { key, ref sig } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 3)?;
+                st.serialize_field("type", "pk")?;
+                st.serialize_field("key", &key.to_string())?;
+                st.serialize_field("sig", &sig.to_string())?;
+            }
+            SatisfiedConstraint::PublicKeyHash {
+                keyhash,
+                ref key,
+                ref sig,
+            } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 4)?;
+                st.serialize_field("type", "pkh")?;
+                st.serialize_field("keyhash", &keyhash.to_string())?;
+                st.serialize_field("key", &key.to_string())?;
+                st.serialize_field("sig", &sig.to_string())?;
+            }
+            SatisfiedConstraint::HashLock { hash, preimage } => {
+                let (ty, hash) = match hash {
+                    HashLockType::Sha256(h) => ("sha256", h.to_string()),
+                    HashLockType::Hash256(h) => ("hash256", h.to_string()),
+                    HashLockType::Hash160(h) => ("hash160", h.to_string()),
+                    HashLockType::Ripemd160(h) => ("ripemd160", h.to_string()),
+                };
+                st = serializer.serialize_struct("SatisfiedConstraint", 3)?;
+                st.serialize_field("type", ty)?;
+                st.serialize_field("hash", &hash)?;
+                st.serialize_field("preimage", &preimage.to_hex())?;
+            }
+            SatisfiedConstraint::RelativeTimeLock { time } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 2)?;
+                st.serialize_field("type", "older")?;
+                st.serialize_field("time", time)?;
+            }
+            SatisfiedConstraint::AbsoluteTimeLock { time } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 2)?;
+                st.serialize_field("type", "after")?;
+                st.serialize_field("time", time)?;
+            }
+            SatisfiedConstraint::VerEq { n } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 2)?;
+                st.serialize_field("type", "ver_eq")?;
+                st.serialize_field("n", n)?;
+            }
+            SatisfiedConstraint::OutputsPref { pref } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 2)?;
+                st.serialize_field("type", "outputs_pref")?;
+                st.serialize_field("pref", &pref.to_hex())?;
+            }
+            SatisfiedConstraint::Ext { ext } => {
+                st = serializer.serialize_struct("SatisfiedConstraint", 2)?;
+                st.serialize_field("type", "ext")?;
+                st.serialize_field("ext", &ext.to_string())?;
+            }
+        }
+        st.end()
+    }
+}
+
 ///This is used by the interpreter to know which evaluation state a AstemElem is.
 ///This is required because whenever a same node(for eg. OrB) appears on the stack, we don't
 ///know if the left child has been evaluated or not. And based on the result on
@@ -392,10 +632,11 @@ where
     public_key: Option<&'intp PublicKey>,
     state: Vec<NodeEvaluationState<'intp, Ext>>,
     stack: &'intp mut Stack<'txin>,
-    age: u32,
-    height: u32,
+    lock_time: u32,
+    sequence: u32,
     cov: Option<&'intp PublicKey>,
     has_errored: bool,
+    max_stack_depth: usize,
 }
 
 ///Iterator for Iter
@@ -413,6 +654,7 @@ where
             None
         } else {
             let res = self.iter_next();
+            self.record_stack_depth();
             if let Some(Err(_)) = res {
                 self.has_errored = true;
             }
@@ -441,9 +683,32 @@ where
         })
     }
 
+    /// Helper function to put the result of the left child of an `and_b`
+    /// or `or_b`, which was popped before evaluating the right child, back
+    /// below the result of the right child so the two can be combined
+    fn restore_left_result(&mut self, n_satisfied: usize) -> Result<(), Error> {
+        let right = self.stack.pop().ok_or(Error::UnexpectedStackEnd)?;
+        if n_satisfied == 1 {
+            self.stack.push(stack::Element::Satisfied);
+        } else {
+            self.stack.push(stack::Element::Dissatisfied);
+        }
+        self.stack.push(right);
+        Ok(())
+    }
+
+    /// Helper function to track the largest stack seen so far
+    fn record_stack_depth(&mut self) {
+        let depth = self.stack.len();
+        if depth > self.max_stack_depth {
+            self.max_stack_depth = depth;
+        }
+    }
+
     /// Helper function to step the iterator
     fn iter_next(&mut self) -> Option<Result<SatisfiedConstraint<'intp, 'txin, Ext>, Error>> {
         while let Some(node_state) = self.state.pop() {
+            self.record_stack_depth();
             //non-empty stack
             match node_state.node.node {
                 Terminal::True => {
@@ -475,7 +740,7 @@ where
                 Terminal::After(ref n) => {
                     debug_assert_eq!(node_state.n_evaluated, 0);
                     debug_assert_eq!(node_state.n_satisfied, 0);
-                    let res = self.stack.evaluate_after(n, self.age);
+                    let res = self.stack.evaluate_after(n, self.lock_time);
                     if res.is_some() {
                         return res;
                     }
@@ -483,7 +748,7 @@ where
                 Terminal::Older(ref n) => {
                     debug_assert_eq!(node_state.n_evaluated, 0);
                     debug_assert_eq!(node_state.n_satisfied, 0);
-                    let res = self.stack.evaluate_older(n, self.height);
+                    let res = self.stack.evaluate_older(n, self.sequence);
                     if res.is_some() {
                         return res;
                     }
@@ -541,7 +806,7 @@ where
                         self.push_evaluation_state(node_state.node, 1, 1);
                         self.push_evaluation_state(sub, 0, 0);
                     }
-                    Some(stack::Element::Push(_v)) => {
+                    Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                         return Some(Err(Error::UnexpectedStackElementPush))
                     }
                     None => return Some(Err(Error::UnexpectedStackEnd)),
@@ -604,19 +869,18 @@ where
                             self.push_evaluation_state(node_state.node, 2, 1);
                             self.push_evaluation_state(right, 0, 0);
                         }
-                        Some(stack::Element::Push(_v)) => {
+                        Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                             return Some(Err(Error::UnexpectedStackElementPush))
                         }
                         None => return Some(Err(Error::UnexpectedStackEnd)),
                     }
                 }
                 Terminal::AndB(ref _left, ref _right) if node_state.n_evaluated == 2 => {
-                    match self.stack.pop() {
-                        Some(stack::Element::Satisfied) if node_state.n_satisfied == 1 => {
-                            self.stack.push(stack::Element::Satisfied)
-                        }
-                        Some(_) => self.stack.push(stack::Element::Dissatisfied),
-                        None => return Some(Err(Error::UnexpectedStackEnd)),
+                    if let Err(e) = self.restore_left_result(node_state.n_satisfied) {
+                        return Some(Err(e));
+                    }
+                    if let Err(e) = self.stack.evaluate_booland() {
+                        return Some(Err(e));
                     }
                 }
                 Terminal::AndOr(ref left, ref _right, _)
@@ -628,14 +892,11 @@ where
                     self.push_evaluation_state(left, 0, 0);
                 }
                 Terminal::OrB(ref _left, ref _right) if node_state.n_evaluated == 2 => {
-                    match self.stack.pop() {
-                        Some(stack::Element::Dissatisfied) if node_state.n_satisfied == 0 => {
-                            self.stack.push(stack::Element::Dissatisfied)
-                        }
-                        Some(_) => {
-                            self.stack.push(stack::Element::Satisfied);
-                        }
-                        None => return Some(Err(Error::UnexpectedStackEnd)),
+                    if let Err(e) = self.restore_left_result(node_state.n_satisfied) {
+                        return Some(Err(e));
+                    }
+                    if let Err(e) = self.stack.evaluate_boolor() {
+                        return Some(Err(e));
                     }
                 }
                 Terminal::OrC(ref _left, ref right) if node_state.n_evaluated == 1 => {
@@ -644,7 +905,7 @@ where
                         Some(stack::Element::Dissatisfied) => {
                             self.push_evaluation_state(right, 0, 0)
                         }
-                        Some(stack::Element::Push(_v)) => {
+                        Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                             return Some(Err(Error::UnexpectedStackElementPush))
                         }
                         None => return Some(Err(Error::UnexpectedStackEnd)),
@@ -658,7 +919,7 @@ where
                         Some(stack::Element::Dissatisfied) => {
                             self.push_evaluation_state(right, 0, 0)
                         }
-                        Some(stack::Element::Push(_v)) => {
+                        Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                             return Some(Err(Error::UnexpectedStackElementPush))
                         }
                         None => return Some(Err(Error::UnexpectedStackEnd)),
@@ -670,7 +931,7 @@ where
                         Some(stack::Element::Dissatisfied) => {
                             self.push_evaluation_state(right, 0, 0)
                         }
-                        Some(stack::Element::Push(_v)) => {
+                        Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                             return Some(Err(Error::UnexpectedStackElementPush))
                         }
                         None => return Some(Err(Error::UnexpectedStackEnd)),
@@ -691,7 +952,7 @@ where
                         Some(stack::Element::Satisfied) | Some(stack::Element::Dissatisfied) => {
                             self.stack.push(stack::Element::Dissatisfied)
                         }
-                        Some(stack::Element::Push(_v)) => {
+                        Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                             return Some(Err(Error::UnexpectedStackElementPush))
                         }
                         None => return Some(Err(Error::UnexpectedStackEnd)),
@@ -715,7 +976,7 @@ where
                             );
                             self.push_evaluation_state(&subs[node_state.n_evaluated], 0, 0);
                         }
-                        Some(stack::Element::Push(_v)) => {
+                        Some(stack::Element::Push(_)) | Some(stack::Element::Num(_)) => {
                             return Some(Err(Error::UnexpectedStackElementPush))
                         }
                         None => return Some(Err(Error::UnexpectedStackEnd)),
@@ -739,6 +1000,8 @@ where
                                     .count();
                                 if nonsat == *k + 1 {
                                     self.stack.push(stack::Element::Dissatisfied);
+                                } else if nonsat == *k && sigs[0] != stack::Element::Dissatisfied {
+                                    return Some(Err(Error::NonEmptyDummy));
                                 } else {
                                     return Some(Err(Error::MissingExtraZeroMultiSig));
                                 }
@@ -771,10 +1034,12 @@ where
                 Terminal::Multi(k, ref subs) => {
                     if node_state.n_satisfied == k {
                         //multi-sig bug: Pop extra 0
-                        if let Some(stack::Element::Dissatisfied) = self.stack.pop() {
-                            self.stack.push(stack::Element::Satisfied);
-                        } else {
-                            return Some(Err(Error::MissingExtraZeroMultiSig));
+                        match self.stack.pop() {
+                            Some(stack::Element::Dissatisfied) => {
+                                self.stack.push(stack::Element::Satisfied)
+                            }
+                            Some(..) => return Some(Err(Error::NonEmptyDummy)),
+                            None => return Some(Err(Error::MissingExtraZeroMultiSig)),
                         }
                     } else if node_state.n_evaluated == subs.len() {
                         return Some(Err(Error::MultiSigEvaluationError));
@@ -965,6 +1230,47 @@ mod tests {
         (pks, der_sigs, secp_sigs, msg, secp_verify)
     }
 
+    #[test]
+    fn soft_and_hard_failures() {
+        let (pks, _der_sigs, _secp_sigs, _sighash, _secp) = setup_keys_sigs(1);
+        let preimage = vec![0xab as u8; 32];
+        let wrong_preimage = vec![0xcd as u8; 32];
+        let hash = sha256::Hash::hash(&preimage);
+
+        // A 32-byte preimage with the wrong hash dissatisfies the fragment
+        let mut stack = Stack::from(vec![stack::Element::Push(&wrong_preimage)]);
+        assert!(stack.evaluate_sha256::<AllExt>(&hash).is_none());
+        assert_eq!(stack.last(), Some(&stack::Element::Dissatisfied));
+
+        // A preimage of the wrong size is a hard failure
+        let short_preimage = vec![0xab as u8; 31];
+        let mut stack = Stack::from(vec![stack::Element::Push(&short_preimage)]);
+        match stack.evaluate_sha256::<AllExt>(&hash) {
+            Some(Err(e)) => assert_eq!(e.failure_kind(), FailureKind::HardFail),
+            _ => panic!("expected an error"),
+        }
+
+        // A signature which does not parse is a hard failure
+        let bad_sig = vec![0x30, 0x01, 0x01, 0x01];
+        let mut stack = Stack::from(vec![stack::Element::Push(&bad_sig)]);
+        match stack.evaluate_pk::<_, AllExt>(|_, _| true, &pks[0]) {
+            Some(Err(e)) => assert_eq!(e.failure_kind(), FailureKind::HardFail),
+            _ => panic!("expected an error"),
+        }
+
+        // An unmet timelock is a soft failure
+        let mut stack = Stack::from(vec![]);
+        match stack.evaluate_after::<AllExt>(&1000, 999) {
+            Some(Err(e)) => assert_eq!(e.failure_kind(), FailureKind::SoftFail),
+            _ => panic!("expected an error"),
+        }
+        let mut stack = Stack::from(vec![]);
+        match stack.evaluate_older::<AllExt>(&10, 9) {
+            Some(Err(e)) => assert_eq!(e.failure_kind(), FailureKind::SoftFail),
+            _ => panic!("expected an error"),
+        }
+    }
+
     #[test]
     fn sat_constraints() {
         let (pks, der_sigs, secp_sigs, sighash, secp) = setup_keys_sigs(10);
@@ -988,10 +1294,11 @@ mod tests {
                     n_evaluated: 0,
                     n_satisfied: 0,
                 }],
-                age: 1002,
-                height: 1002,
+                lock_time: 1002,
+                sequence: 1002,
                 cov: None,
                 has_errored: false,
+                max_stack_depth: 0,
             }
         }
 
@@ -1186,6 +1493,30 @@ mod tests {
             ]
         );
 
+        //Check AndB with a timelock
+        let mut stack = Stack::from(vec![stack::Element::Push(&der_sigs[0])]);
+        let elem = ms_str!("and_b(c:pk_k({}),a:older(144))", pks[0]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+
+        let and_b_older: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert_eq!(
+            and_b_older.unwrap(),
+            vec![
+                SatisfiedConstraint::PublicKey {
+                    key: &pks[0],
+                    sig: secp_sigs[0].clone(),
+                },
+                SatisfiedConstraint::RelativeTimeLock { time: &144 },
+            ]
+        );
+
+        let mut stack = Stack::from(vec![stack::Element::Dissatisfied]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let and_b_older: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert!(and_b_older.is_err());
+
         //Check AndOr
         let mut stack = Stack::from(vec![
             stack::Element::Push(&preimage),
@@ -1403,5 +1734,539 @@ mod tests {
 
         let multi_error: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
         assert!(multi_error.is_err());
+
+        // Error multi: non-empty dummy element
+        let dummy = [0u8];
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&dummy),
+            stack::Element::Push(&der_sigs[2]),
+            stack::Element::Push(&der_sigs[1]),
+            stack::Element::Push(&der_sigs[0]),
+        ]);
+        let elem = ms_str!(
+            "multi(3,{},{},{},{},{})",
+            pks[4],
+            pks[3],
+            pks[2],
+            pks[1],
+            pks[0],
+        );
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+
+        let multi_error: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        match multi_error {
+            Err(Error::NonEmptyDummy) => {}
+            x => panic!("expected NonEmptyDummy, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn max_stack_depth() {
+        use std::str::FromStr;
+        use {Descriptor, DescriptorTrait};
+
+        let (pks, der_sigs, _secp_sigs, sighash, secp) = setup_keys_sigs(80);
+        let chunks: Vec<String> = pks
+            .chunks(20)
+            .map(|c| {
+                let keys: Vec<_> = c.iter().map(|pk| pk.to_string()).collect();
+                keys.join(",")
+            })
+            .collect();
+        let ks = [20, 20, 20, 14];
+        let desc = Descriptor::<bitcoin::PublicKey>::from_str(&format!(
+            "elwsh(and_v(v:multi({},{}),and_v(v:multi({},{}),and_v(v:multi({},{}),multi({},{})))))",
+            ks[0], chunks[0], ks[1], chunks[1], ks[2], chunks[2], ks[3], chunks[3],
+        ))
+        .unwrap();
+        // The first multi consumes the top of the stack
+        let mut witness: Vec<Vec<u8>> = vec![];
+        for (i, &k) in ks.iter().enumerate().rev() {
+            witness.push(vec![]);
+            witness.extend(der_sigs[20 * i..20 * i + k].iter().cloned());
+        }
+        witness.push(desc.explicit_script().into_bytes());
+        let spk = desc.script_pubkey();
+        let script_sig = elements::Script::new();
+
+        let mut interpreter = Interpreter::from_txdata(&spk, &script_sig, &witness, 0, 0).unwrap();
+        let depth = interpreter
+            .max_stack_depth(|pk: &bitcoin::PublicKey, (sig, _)| {
+                secp.verify(&sighash, &sig, &pk.key).is_ok()
+            })
+            .unwrap();
+        assert_eq!(depth, 78);
+        // The static estimate also accounts for the keys pushed by each
+        // multi, which the interpreter does not model
+        assert_eq!(desc.max_exec_stack_items().unwrap(), 100);
+    }
+
+    #[test]
+    fn evaluate_left() {
+        let elem = [1u8, 2, 3, 4, 5, 6, 7, 8];
+        let four = [4u8];
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&elem),
+            stack::Element::Push(&four),
+        ]);
+        stack.evaluate_left().unwrap();
+        assert_eq!(stack, Stack::from(vec![stack::Element::Push(&elem[..4])]));
+
+        // Slicing the whole element is fine
+        let eight = [8u8];
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&elem),
+            stack::Element::Push(&eight),
+        ]);
+        stack.evaluate_left().unwrap();
+        assert_eq!(stack, Stack::from(vec![stack::Element::Push(&elem)]));
+
+        // Out of range
+        let nine = [9u8];
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&elem),
+            stack::Element::Push(&nine),
+        ]);
+        match stack.evaluate_left() {
+            Err(Error::SubstrOutOfRange { n: 9, len: 8 }) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        // Negative length
+        let neg = [0x81u8];
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&elem),
+            stack::Element::Push(&neg),
+        ]);
+        assert!(stack.evaluate_left().is_err());
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        // <100> <44> ADD <144> EQUAL
+        let a = [100u8];
+        let b = [44u8];
+        let target = [0x90u8, 0x00];
+        let mut stack = Stack::from(vec![stack::Element::Push(&a), stack::Element::Push(&b)]);
+        stack.evaluate_add().unwrap();
+        assert_eq!(stack.last(), Some(&stack::Element::Num(144)));
+        stack.push(stack::Element::Push(&target));
+        stack.evaluate_equal().unwrap();
+        assert_eq!(stack, Stack::from(vec![stack::Element::Satisfied]));
+
+        // <100> <99> SUB is pushed as a boolean 1
+        let c = [99u8];
+        let mut stack = Stack::from(vec![stack::Element::Push(&a), stack::Element::Push(&c)]);
+        stack.evaluate_sub().unwrap();
+        assert_eq!(stack, Stack::from(vec![stack::Element::Satisfied]));
+
+        // Results may exceed 4 bytes but cannot be used as operands
+        let max = [0xffu8, 0xff, 0xff, 0x7f];
+        let mut stack = Stack::from(vec![stack::Element::Push(&max), stack::Element::Push(&max)]);
+        stack.evaluate_add().unwrap();
+        stack.push(stack::Element::Push(&a));
+        match stack.evaluate_add() {
+            Err(Error::NumOverflow(0xfffffffe)) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn evaluate_stack_ops() {
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+
+        // <a> DUP EQUAL
+        let mut stack = Stack::from(vec![stack::Element::Push(&a)]);
+        stack.evaluate_dup().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Push(&a), stack::Element::Push(&a)])
+        );
+        stack.evaluate_equal().unwrap();
+        assert_eq!(stack, Stack::from(vec![stack::Element::Satisfied]));
+
+        // <a> <b> SWAP, then OVER and DROP
+        let mut stack = Stack::from(vec![stack::Element::Push(&a), stack::Element::Push(&b)]);
+        stack.evaluate_swap().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Push(&b), stack::Element::Push(&a)])
+        );
+        stack.evaluate_over().unwrap();
+        assert_eq!(stack.last(), Some(&stack::Element::Push(&b)));
+        stack.evaluate_drop().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Push(&b), stack::Element::Push(&a)])
+        );
+
+        // <a> <b> TOALTSTACK TOALTSTACK FROMALTSTACK FROMALTSTACK
+        let mut stack = Stack::from(vec![stack::Element::Push(&a), stack::Element::Push(&b)]);
+        let mut altstack = Stack::default();
+        stack.evaluate_toaltstack(&mut altstack).unwrap();
+        stack.evaluate_toaltstack(&mut altstack).unwrap();
+        assert!(stack.is_empty());
+        assert_eq!(
+            altstack,
+            Stack::from(vec![stack::Element::Push(&b), stack::Element::Push(&a)])
+        );
+        stack.evaluate_fromaltstack(&mut altstack).unwrap();
+        stack.evaluate_fromaltstack(&mut altstack).unwrap();
+        assert!(altstack.is_empty());
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Push(&a), stack::Element::Push(&b)])
+        );
+
+        // Not enough elements
+        match stack.evaluate_fromaltstack(&mut altstack) {
+            Err(Error::UnexpectedStackEnd) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+        let mut stack = Stack::from(vec![stack::Element::Push(&a)]);
+        assert!(stack.evaluate_over().is_err());
+        assert!(stack.evaluate_swap().is_err());
+    }
+
+    #[test]
+    fn evaluate_size() {
+        // <a> SIZE 8 EQUAL, leaving the measured element in place
+        let a = [0xffu8; 8];
+        let eight = [8u8];
+        let mut stack = Stack::from(vec![stack::Element::Push(&a)]);
+        stack.evaluate_size().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Push(&a), stack::Element::Num(8)])
+        );
+        stack.push(stack::Element::Push(&eight));
+        stack.evaluate_equal().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Push(&a), stack::Element::Satisfied])
+        );
+
+        // The empty push has size 0 and `1` has size 1
+        let mut stack = Stack::from(vec![stack::Element::Dissatisfied]);
+        stack.evaluate_size().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![
+                stack::Element::Dissatisfied,
+                stack::Element::Dissatisfied
+            ])
+        );
+        let mut stack = Stack::from(vec![stack::Element::Satisfied]);
+        stack.evaluate_size().unwrap();
+        assert_eq!(
+            stack,
+            Stack::from(vec![stack::Element::Satisfied, stack::Element::Satisfied])
+        );
+
+        // Computed numbers are measured by their minimal encoding
+        let mut stack = Stack::from(vec![stack::Element::Num(-0x80)]);
+        stack.evaluate_size().unwrap();
+        assert_eq!(stack.last(), Some(&stack::Element::Num(2)));
+
+        let mut stack = Stack::default();
+        match stack.evaluate_size() {
+            Err(Error::UnexpectedStackEnd) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn sat_constraints_wrappers() {
+        let (pks, der_sigs, secp_sigs, sighash, secp) = setup_keys_sigs(2);
+        let vfyfn_ =
+            |pk: &bitcoin::PublicKey, (sig, _)| secp.verify(&sighash, &sig, &pk.key).is_ok();
+
+        fn from_stack<'txin, 'elem, F>(
+            verify_fn: F,
+            stack: &'elem mut Stack<'txin>,
+            ms: &'elem Miniscript<bitcoin::PublicKey, NoChecks, AllExt>,
+        ) -> Iter<'elem, 'txin, AllExt, F>
+        where
+            F: FnMut(&bitcoin::PublicKey, ElementsSig) -> bool,
+        {
+            Iter {
+                verify_sig: verify_fn,
+                stack: stack,
+                public_key: None,
+                state: vec![NodeEvaluationState {
+                    node: ms,
+                    n_evaluated: 0,
+                    n_satisfied: 0,
+                }],
+                lock_time: 1002,
+                sequence: 1002,
+                cov: None,
+                has_errored: false,
+                max_stack_depth: 0,
+            }
+        }
+
+        //Check n: wrapper
+        let mut stack = Stack::from(vec![stack::Element::Push(&der_sigs[0])]);
+        let elem = ms_str!("nc:pk_k({})", pks[0]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let n_satisfied: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert_eq!(
+            n_satisfied.unwrap(),
+            vec![SatisfiedConstraint::PublicKey {
+                key: &pks[0],
+                sig: secp_sigs[0].clone(),
+            }]
+        );
+        let mut stack = Stack::from(vec![stack::Element::Dissatisfied]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let n_err: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert!(n_err.is_err());
+
+        //Check j: wrapper, skipping the wrapped fragment on an empty push
+        let elem = ms_str!("or_d(jc:pk_k({}),c:pk_k({}))", pks[0], pks[1]);
+        let mut stack = Stack::from(vec![stack::Element::Push(&der_sigs[0])]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let j_satisfied: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert_eq!(
+            j_satisfied.unwrap(),
+            vec![SatisfiedConstraint::PublicKey {
+                key: &pks[0],
+                sig: secp_sigs[0].clone(),
+            }]
+        );
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&der_sigs[1]),
+            stack::Element::Dissatisfied,
+        ]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let j_dissatisfied: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert_eq!(
+            j_dissatisfied.unwrap(),
+            vec![SatisfiedConstraint::PublicKey {
+                key: &pks[1],
+                sig: secp_sigs[1].clone(),
+            }]
+        );
+
+        //Check l: and u: wrappers, whose other branch is 0
+        let elem = ms_str!("lc:pk_k({})", pks[0]);
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&der_sigs[0]),
+            stack::Element::Dissatisfied,
+        ]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let l_satisfied: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert_eq!(
+            l_satisfied.unwrap(),
+            vec![SatisfiedConstraint::PublicKey {
+                key: &pks[0],
+                sig: secp_sigs[0].clone(),
+            }]
+        );
+        let mut stack = Stack::from(vec![stack::Element::Satisfied]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let l_err: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert!(l_err.is_err());
+
+        let elem = ms_str!("uc:pk_k({})", pks[0]);
+        let mut stack = Stack::from(vec![
+            stack::Element::Push(&der_sigs[0]),
+            stack::Element::Satisfied,
+        ]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let u_satisfied: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert_eq!(
+            u_satisfied.unwrap(),
+            vec![SatisfiedConstraint::PublicKey {
+                key: &pks[0],
+                sig: secp_sigs[0].clone(),
+            }]
+        );
+        let mut stack = Stack::from(vec![stack::Element::Dissatisfied]);
+        let mut vfyfn = vfyfn_.clone(); // sigh rust 1.29...
+        let constraints = from_stack(&mut vfyfn, &mut stack, &elem);
+        let u_err: Result<Vec<SatisfiedConstraint<AllExt>>, Error> = constraints.collect();
+        assert!(u_err.is_err());
+    }
+
+    #[test]
+    fn sighash_types() {
+        use std::str::FromStr;
+        use {Descriptor, DescriptorTrait};
+
+        let (pks, der_sigs, _secp_sigs, sighash, secp) = setup_keys_sigs(3);
+        let desc = Descriptor::<bitcoin::PublicKey>::from_str(&format!(
+            "elwsh(multi(2,{},{},{}))",
+            pks[0], pks[1], pks[2]
+        ))
+        .unwrap();
+        let spk = desc.script_pubkey();
+        let script_sig = elements::Script::new();
+        let vfyfn = |pk: &bitcoin::PublicKey, (sig, _): ElementsSig| {
+            secp.verify(&sighash, &sig, &pk.key).is_ok()
+        };
+
+        let mut none_sig = der_sigs[1].clone();
+        *none_sig.last_mut().unwrap() = SigHashType::None as u8;
+        let witness = vec![
+            vec![],
+            der_sigs[0].clone(),
+            none_sig,
+            desc.explicit_script().into_bytes(),
+        ];
+        let mut interpreter = Interpreter::from_txdata(&spk, &script_sig, &witness, 0, 0).unwrap();
+        let types = interpreter.sighash_types(&vfyfn).unwrap();
+        assert_eq!(types.len(), 2);
+        assert!(types.contains(&SigHashType::All));
+        assert!(types.contains(&SigHashType::None));
+
+        let witness = vec![
+            vec![],
+            der_sigs[0].clone(),
+            der_sigs[2].clone(),
+            desc.explicit_script().into_bytes(),
+        ];
+        let mut interpreter = Interpreter::from_txdata(&spk, &script_sig, &witness, 0, 0).unwrap();
+        assert_eq!(
+            interpreter.sighash_types(&vfyfn).unwrap(),
+            vec![SigHashType::All]
+        );
+    }
+
+    #[test]
+    fn authorization_summary() {
+        use std::str::FromStr;
+        use {Descriptor, DescriptorTrait};
+
+        let (pks, der_sigs, _secp_sigs, sighash, secp) = setup_keys_sigs(3);
+        let desc = Descriptor::<bitcoin::PublicKey>::from_str(&format!(
+            "elwsh(thresh(2,pk({}),s:pk({}),s:pk({})))",
+            pks[0], pks[1], pks[2]
+        ))
+        .unwrap();
+        // The first fragment is satisfied by the top of the stack
+        let witness = vec![
+            vec![],
+            der_sigs[1].clone(),
+            der_sigs[0].clone(),
+            desc.explicit_script().into_bytes(),
+        ];
+        let spk = desc.script_pubkey();
+        let script_sig = elements::Script::new();
+        let mut interpreter = Interpreter::from_txdata(&spk, &script_sig, &witness, 0, 0).unwrap();
+        let summary = interpreter
+            .authorization_summary(|pk: &bitcoin::PublicKey, (sig, _)| {
+                secp.verify(&sighash, &sig, &pk.key).is_ok()
+            })
+            .unwrap();
+        assert_eq!(
+            summary,
+            AuthSummary {
+                signers: vec![pks[0], pks[1]],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            format!("signed by [{},{}]", pks[0], pks[1])
+        );
+    }
+
+    #[test]
+    fn evaluate_bool_combinators() {
+        let sat = stack::Element::Satisfied;
+        let dissat = stack::Element::Dissatisfied;
+        for &(a, b, and, or) in &[
+            (sat, sat, sat, sat),
+            (sat, dissat, dissat, sat),
+            (dissat, sat, dissat, sat),
+            (dissat, dissat, dissat, dissat),
+        ] {
+            let mut stack = Stack::from(vec![a, b]);
+            stack.evaluate_booland().unwrap();
+            assert_eq!(stack, Stack::from(vec![and]));
+            let mut stack = Stack::from(vec![a, b]);
+            stack.evaluate_boolor().unwrap();
+            assert_eq!(stack, Stack::from(vec![or]));
+        }
+
+        // Subexpression results are always booleans
+        let push = [2u8];
+        let mut stack = Stack::from(vec![sat, stack::Element::Push(&push)]);
+        match stack.evaluate_booland() {
+            Err(Error::UnexpectedStackElementPush) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+        let mut stack = Stack::from(vec![sat]);
+        match stack.evaluate_boolor() {
+            Err(Error::UnexpectedStackEnd) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn evaluate_older_lock_types() {
+        fn older(n: &u32, sequence: u32) -> Result<SatisfiedConstraint<AllExt>, Error> {
+            let mut stack = Stack::from(vec![]);
+            stack.evaluate_older(n, sequence).unwrap()
+        }
+        // Time based: 10 * 512 seconds
+        let time = (1 << 22) | 10;
+        assert_eq!(
+            older(&time, (1 << 22) | 12).unwrap(),
+            SatisfiedConstraint::RelativeTimeLock { time: &time }
+        );
+        match older(&time, (1 << 22) | 9) {
+            Err(Error::RelativeLocktimeNotMet(n)) if n == time => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+        // A height based sequence does not satisfy a time based lock,
+        // even though it is numerically larger
+        assert!(older(&time, 1000).is_err());
+
+        // Height based: 144 blocks
+        let height = 144;
+        assert_eq!(
+            older(&height, 144).unwrap(),
+            SatisfiedConstraint::RelativeTimeLock { time: &height }
+        );
+        assert!(older(&height, (1 << 22) | 144).is_err());
+        // Relative locktime disabled for the input
+        assert!(older(&height, (1 << 31) | 144).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "use-serde")]
+    fn constraints_json() {
+        let (pks, der_sigs, secp_sigs, sighash, secp) = setup_keys_sigs(1);
+        let spk = elements::Script::new_p2pk(&pks[0]);
+        let script_sig = elements::script::Builder::new()
+            .push_slice(&der_sigs[0])
+            .into_script();
+        let witness: Vec<Vec<u8>> = vec![];
+
+        let mut interpreter = Interpreter::from_txdata(&spk, &script_sig, &witness, 0, 0).unwrap();
+        let json = interpreter
+            .constraints_json(|pk: &bitcoin::PublicKey, (sig, _)| {
+                secp.verify(&sighash, &sig, &pk.key).is_ok()
+            })
+            .unwrap();
+        assert_eq!(
+            json,
+            format!(
+                "[{{\"type\":\"pk\",\"key\":\"{}\",\"sig\":\"{}\"}}]",
+                pks[0], secp_sigs[0],
+            )
+        );
     }
 }
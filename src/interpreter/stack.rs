@@ -20,11 +20,19 @@ use bitcoin::{self, PublicKey};
 use elements::hashes::{hash160, ripemd160, sha256, sha256d, Hash};
 use elements::{self, opcodes, script};
 
+use miniscript::limits::{
+    SEQUENCE_LOCKTIME_DISABLE_FLAG, SEQUENCE_LOCKTIME_MASK, SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
+use util::build_scriptint;
 use {ElementsSig, ToPublicKey};
 
 use Extension;
 
 use super::{verify_sersig, Error, HashLockType, SatisfiedConstraint};
+
+/// Largest magnitude of a 4-byte script number, the maximum size of
+/// operands to arithmetic opcodes
+const MAX_SCRIPTNUM: i64 = 0x7fff_ffff;
 /// Definition of Stack Element of the Stack used for interpretation of Miniscript.
 /// All stack elements with vec![] go to Dissatisfied and vec![1] are marked to Satisfied.
 /// Others are directly pushed as witness
@@ -38,6 +46,9 @@ pub enum Element<'txin> {
     Dissatisfied,
     /// Input from the witness stack
     Push(&'txin [u8]),
+    /// Result of an arithmetic operation such as `OP_ADD`, other than
+    /// `0` and `1` which are represented by `Dissatisfied` and `Satisfied`
+    Num(i64),
 }
 
 impl<'txin> From<&'txin Vec<u8>> for Element<'txin> {
@@ -90,11 +101,21 @@ impl<'txin> Element<'txin> {
     }
 
     /// Convert element into slice
-    pub(crate) fn into_slice(self) -> &'txin [u8] {
+    /// Errs for computed numbers, which are not backed by witness data
+    pub(crate) fn into_slice(self) -> Result<&'txin [u8], Error> {
         match self {
-            Element::Satisfied => &[1],
-            Element::Dissatisfied => &[],
-            Element::Push(ref v) => v,
+            Element::Satisfied => Ok(&[1]),
+            Element::Dissatisfied => Ok(&[]),
+            Element::Push(ref v) => Ok(v),
+            Element::Num(_) => Err(Error::ExpectedPush),
+        }
+    }
+
+    /// The script encoding of the element
+    fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            Element::Num(n) => build_scriptint(n),
+            ref x => x.into_slice().expect("not a number").to_vec(),
         }
     }
 }
@@ -156,11 +177,197 @@ impl<'txin> Stack<'txin> {
         self.0.last()
     }
 
+    /// Pops the top stack element and interprets it as a script number
+    fn pop_num(&mut self) -> Result<i64, Error> {
+        match self.pop() {
+            Some(Element::Satisfied) => Ok(1),
+            Some(Element::Dissatisfied) => Ok(0),
+            Some(Element::Push(v)) => script::read_scriptint(v).map_err(|_| Error::ExpectedNum),
+            // Script numbers used as operands are limited to 4 bytes
+            Some(Element::Num(n)) if n > MAX_SCRIPTNUM || n < -MAX_SCRIPTNUM => {
+                Err(Error::NumOverflow(n))
+            }
+            Some(Element::Num(n)) => Ok(n),
+            None => Err(Error::UnexpectedStackEnd),
+        }
+    }
+
+    /// Pushes the result of an arithmetic operation
+    fn push_num(&mut self, n: i64) {
+        match n {
+            0 => self.push(Element::Dissatisfied),
+            1 => self.push(Element::Satisfied),
+            n => self.push(Element::Num(n)),
+        }
+    }
+
+    /// Helper function to evaluate `OP_ADD`. Pops two script numbers
+    /// and pushes their sum.
+    /// `<a> <b> ADD`
+    pub fn evaluate_add(&mut self) -> Result<(), Error> {
+        let b = self.pop_num()?;
+        let a = self.pop_num()?;
+        self.push_num(a + b);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_SUB`. Pops two script numbers
+    /// and pushes their difference.
+    /// `<a> <b> SUB`
+    pub fn evaluate_sub(&mut self) -> Result<(), Error> {
+        let b = self.pop_num()?;
+        let a = self.pop_num()?;
+        self.push_num(a - b);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_EQUAL`. Pops two elements and
+    /// pushes whether their encodings are byte-wise equal.
+    /// `<a> <b> EQUAL`
+    pub fn evaluate_equal(&mut self) -> Result<(), Error> {
+        let b = self.pop().ok_or(Error::UnexpectedStackEnd)?;
+        let a = self.pop().ok_or(Error::UnexpectedStackEnd)?;
+        if a.to_bytes() == b.to_bytes() {
+            self.push(Element::Satisfied);
+        } else {
+            self.push(Element::Dissatisfied);
+        }
+        Ok(())
+    }
+
+    /// Pops the top stack element, which must be the boolean result
+    /// of a subexpression
+    fn pop_bool(&mut self) -> Result<bool, Error> {
+        match self.pop() {
+            Some(Element::Satisfied) => Ok(true),
+            Some(Element::Dissatisfied) => Ok(false),
+            Some(Element::Push(_)) | Some(Element::Num(_)) => {
+                Err(Error::UnexpectedStackElementPush)
+            }
+            None => Err(Error::UnexpectedStackEnd),
+        }
+    }
+
+    /// Helper function to evaluate `OP_BOOLAND`, as used by `and_b`. Pops
+    /// the results of the two subexpressions and pushes `Satisfied` if
+    /// both of them were satisfied.
+    /// `<a> <b> BOOLAND`
+    pub fn evaluate_booland(&mut self) -> Result<(), Error> {
+        let b = self.pop_bool()?;
+        let a = self.pop_bool()?;
+        if a && b {
+            self.push(Element::Satisfied);
+        } else {
+            self.push(Element::Dissatisfied);
+        }
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_BOOLOR`, as used by `or_b`. Pops
+    /// the results of the two subexpressions and pushes `Satisfied` if
+    /// either of them was satisfied.
+    /// `<a> <b> BOOLOR`
+    pub fn evaluate_boolor(&mut self) -> Result<(), Error> {
+        let b = self.pop_bool()?;
+        let a = self.pop_bool()?;
+        if a || b {
+            self.push(Element::Satisfied);
+        } else {
+            self.push(Element::Dissatisfied);
+        }
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_LEFT`. Pops the length `n` and the
+    /// element below it, and pushes the first `n` bytes of that element.
+    /// Errs if `n` is negative or greater than the length of the element.
+    /// `<elem> <n> LEFT`
+    pub fn evaluate_left(&mut self) -> Result<(), Error> {
+        let n = self.pop_num()?;
+        let elem = self.pop().ok_or(Error::UnexpectedStackEnd)?.into_slice()?;
+        if n < 0 || n as usize > elem.len() {
+            return Err(Error::SubstrOutOfRange { n, len: elem.len() });
+        }
+        self.push(Element::from(&elem[..n as usize]));
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_DUP`. Pushes a copy of the top
+    /// stack element.
+    /// `<a> DUP`
+    pub fn evaluate_dup(&mut self) -> Result<(), Error> {
+        let a = *self.last().ok_or(Error::UnexpectedStackEnd)?;
+        self.push(a);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_DROP`. Removes the top stack element.
+    /// `<a> DROP`
+    pub fn evaluate_drop(&mut self) -> Result<(), Error> {
+        self.pop().ok_or(Error::UnexpectedStackEnd)?;
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_SWAP`. Exchanges the top two stack
+    /// elements.
+    /// `<a> <b> SWAP`
+    pub fn evaluate_swap(&mut self) -> Result<(), Error> {
+        let b = self.pop().ok_or(Error::UnexpectedStackEnd)?;
+        let a = self.pop().ok_or(Error::UnexpectedStackEnd)?;
+        self.push(b);
+        self.push(a);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_OVER`. Pushes a copy of the second
+    /// to top stack element.
+    /// `<a> <b> OVER`
+    pub fn evaluate_over(&mut self) -> Result<(), Error> {
+        if self.0.len() < 2 {
+            return Err(Error::UnexpectedStackEnd);
+        }
+        let a = self.0[self.0.len() - 2];
+        self.push(a);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_TOALTSTACK`. Moves the top stack
+    /// element onto the top of `altstack`.
+    /// `<a> TOALTSTACK`
+    pub fn evaluate_toaltstack(&mut self, altstack: &mut Stack<'txin>) -> Result<(), Error> {
+        let a = self.pop().ok_or(Error::UnexpectedStackEnd)?;
+        altstack.push(a);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_FROMALTSTACK`. Moves the top element
+    /// of `altstack` onto the top of the stack.
+    /// `FROMALTSTACK`
+    pub fn evaluate_fromaltstack(&mut self, altstack: &mut Stack<'txin>) -> Result<(), Error> {
+        let a = altstack.pop().ok_or(Error::UnexpectedStackEnd)?;
+        self.push(a);
+        Ok(())
+    }
+
+    /// Helper function to evaluate `OP_SIZE`. Pushes the length in bytes
+    /// of the top stack element, without removing it.
+    /// `<a> SIZE`
+    pub fn evaluate_size(&mut self) -> Result<(), Error> {
+        let len = self
+            .last()
+            .ok_or(Error::UnexpectedStackEnd)?
+            .to_bytes()
+            .len();
+        self.push_num(len as i64);
+        Ok(())
+    }
+
     /// Helper function to evaluate a Pk Node which takes the
     /// top of the stack as input signature and validates it.
     /// Sat: If the signature witness is correct, 1 is pushed
     /// Unsat: For empty witness a 0 is pushed
-    /// Err: All of other witness result in errors.
+    /// Err: All of other witness result in errors. These are hard failures,
+    /// as an invalid non-empty signature fails the script (`NULLFAIL`).
     /// `pk` CHECKSIG
     pub fn evaluate_pk<'intp, F, Ext: Extension<PublicKey>>(
         &mut self,
@@ -186,7 +393,7 @@ impl<'txin> Stack<'txin> {
                         Err(e) => return Some(Err(e)),
                     }
                 }
-                Element::Satisfied => {
+                Element::Satisfied | Element::Num(_) => {
                     return Some(Err(Error::PkEvaluationError(pk.clone().to_public_key())))
                 }
             }
@@ -236,7 +443,7 @@ impl<'txin> Stack<'txin> {
                                     Err(e) => return Some(Err(e)),
                                 }
                             }
-                            Element::Satisfied => {
+                            Element::Satisfied | Element::Num(_) => {
                                 return Some(Err(Error::PkEvaluationError(
                                     pk.clone().to_public_key(),
                                 )))
@@ -259,12 +466,15 @@ impl<'txin> Stack<'txin> {
     /// The reason we don't need to copy the Script semantics is that
     /// Miniscript never evaluates integers and it is safe to treat them as
     /// booleans
+    ///
+    /// A lock which is not met is a soft failure: the same witness may be
+    /// valid for a later spending transaction.
     pub fn evaluate_after<'intp, Ext: Extension<PublicKey>>(
         &mut self,
         n: &'intp u32,
-        age: u32,
+        lock_time: u32,
     ) -> Option<Result<SatisfiedConstraint<'intp, 'txin, Ext>, Error>> {
-        if age >= *n {
+        if lock_time >= *n {
             self.push(Element::Satisfied);
             Some(Ok(SatisfiedConstraint::AbsoluteTimeLock { time: n }))
         } else {
@@ -278,12 +488,24 @@ impl<'txin> Stack<'txin> {
     /// The reason we don't need to copy the Script semantics is that
     /// Miniscript never evaluates integers and it is safe to treat them as
     /// booleans
+    ///
+    /// A lock which is not met is a soft failure: the same witness may be
+    /// valid for a spending input with a larger `nSequence`.
+    ///
+    /// `sequence` is the `nSequence` of the spending input. As in BIP 112,
+    /// the lock is only met if relative locktimes are enabled for the input,
+    /// both values are of the same type (blocks or 512-second units) and
+    /// the input's lock is at least `n`.
     pub fn evaluate_older<'intp, Ext: Extension<PublicKey>>(
         &mut self,
         n: &'intp u32,
-        height: u32,
+        sequence: u32,
     ) -> Option<Result<SatisfiedConstraint<'intp, 'txin, Ext>, Error>> {
-        if height >= *n {
+        let type_mismatch = (*n ^ sequence) & SEQUENCE_LOCKTIME_TYPE_FLAG != 0;
+        let is_met = sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG == 0
+            && !type_mismatch
+            && sequence & SEQUENCE_LOCKTIME_MASK >= *n & SEQUENCE_LOCKTIME_MASK;
+        if is_met {
             self.push(Element::Satisfied);
             Some(Ok(SatisfiedConstraint::RelativeTimeLock { time: n }))
         } else {
@@ -292,6 +514,9 @@ impl<'txin> Stack<'txin> {
     }
 
     /// Helper function to evaluate a Sha256 Node.
+    /// Sat: If the SHA256 of the 32-byte preimage is `hash`, 1 is pushed
+    /// Unsat: For any other 32-byte preimage, 0 is pushed
+    /// Err: A preimage of another size fails the `SIZE` check, a hard failure
     /// `SIZE 32 EQUALVERIFY SHA256 h EQUAL`
     pub fn evaluate_sha256<'intp, Ext: Extension<PublicKey>>(
         &mut self,
@@ -317,6 +542,9 @@ impl<'txin> Stack<'txin> {
     }
 
     /// Helper function to evaluate a Hash256 Node.
+    /// Sat: If the double SHA256 of the 32-byte preimage is `hash`, 1 is pushed
+    /// Unsat: For any other 32-byte preimage, 0 is pushed
+    /// Err: A preimage of another size fails the `SIZE` check, a hard failure
     /// `SIZE 32 EQUALVERIFY HASH256 h EQUAL`
     pub fn evaluate_hash256<'intp, Ext: Extension<PublicKey>>(
         &mut self,
@@ -341,7 +569,11 @@ impl<'txin> Stack<'txin> {
         }
     }
 
-    /// Helper function to evaluate a Hash160 Node.
+    /// Helper function to evaluate a Hash160 Node. Although the hash is
+    /// only 20 bytes, Miniscript still requires a 32-byte preimage.
+    /// Sat: If the RIPEMD160 of the SHA256 of the preimage is `hash`, 1 is pushed
+    /// Unsat: For any other 32-byte preimage, 0 is pushed
+    /// Err: A preimage of another size fails the `SIZE` check, a hard failure
     /// `SIZE 32 EQUALVERIFY HASH160 h EQUAL`
     pub fn evaluate_hash160<'intp, Ext: Extension<PublicKey>>(
         &mut self,
@@ -366,7 +598,11 @@ impl<'txin> Stack<'txin> {
         }
     }
 
-    /// Helper function to evaluate a RipeMd160 Node.
+    /// Helper function to evaluate a RipeMd160 Node. The preimage is
+    /// hashed with RIPEMD160 alone, but must also be 32 bytes long.
+    /// Sat: If the RIPEMD160 of the preimage is `hash`, 1 is pushed
+    /// Unsat: For any other 32-byte preimage, 0 is pushed
+    /// Err: A preimage of another size fails the `SIZE` check, a hard failure
     /// `SIZE 32 EQUALVERIFY RIPEMD160 h EQUAL`
     pub fn evaluate_ripemd160<'intp, Ext: Extension<PublicKey>>(
         &mut self,
@@ -397,6 +633,10 @@ impl<'txin> Stack<'txin> {
     /// other signatures are not checked against the first pubkey.
     /// `multi(2,pk1,pk2)` would be satisfied by `[0 sig2 sig1]` and Err on
     /// `[0 sig2 sig1]`
+    ///
+    /// A signature which does not verify against `pk` (including one which
+    /// does not parse) is not an error: `None` is returned and the signature
+    /// is left on the stack to be checked against the next key.
     pub fn evaluate_multi<'intp, F, Ext: Extension<PublicKey>>(
         &mut self,
         verify_sig: F,
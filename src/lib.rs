@@ -112,6 +112,8 @@ pub extern crate bitcoin;
 pub extern crate elements;
 #[cfg(feature = "serde")]
 pub extern crate serde;
+#[cfg(feature = "use-serde")]
+extern crate serde_json;
 #[cfg(all(test, feature = "unstable"))]
 extern crate test;
 
@@ -149,6 +151,7 @@ pub mod interpreter;
 pub mod miniscript;
 pub mod policy;
 pub mod pset;
+pub mod tx;
 
 mod util;
 
@@ -220,8 +223,12 @@ where
 /// Miniscript
 #[derive(Debug)]
 pub enum Error {
-    /// Opcode appeared which is not part of the script subset
-    InvalidOpcode(opcodes::All),
+    /// Opcode appeared which is valid in Elements script but not part of
+    /// the script subset used by Miniscript
+    UnimplementedOpcode(opcodes::All),
+    /// Opcode appeared which is disabled in Elements script, making the
+    /// script invalid
+    DisabledOpcode(opcodes::All),
     /// Some opcode occurred followed by `OP_VERIFY` when it had
     /// a `VERIFY` version that should have been used instead
     NonMinimalVerify(miniscript::lex::Token),
@@ -273,8 +280,18 @@ pub enum Error {
     TypeCheck(String),
     /// General error in creating descriptor
     BadDescriptor(String),
+    /// A descriptor wrapper was nested where consensus or the descriptor
+    /// language does not allow it, e.g. `elsh(elsh(..))` or `elwsh(wpkh(..))`
+    InvalidNesting {
+        /// The enclosing descriptor
+        outer: String,
+        /// The nested descriptor wrapper
+        inner: String,
+    },
     /// Forward-secp related errors
     Secp(elements::secp256k1_zkp::Error),
+    /// Failed to blind the outputs of a transaction
+    BlindError(String),
     #[cfg(feature = "compiler")]
     /// Compiler related errors
     CompilerError(policy::compiler::CompilerError),
@@ -284,10 +301,22 @@ pub enum Error {
     LiftError(policy::LiftError),
     /// Forward script context related errors
     ContextError(miniscript::context::ScriptContextError),
-    /// Recursion depth exceeded when parsing policy/miniscript from string
+    /// Recursion depth exceeded when parsing policy/miniscript from string,
+    /// by default beyond 402 levels of nesting
     MaxRecursiveDepthExceeded,
+    /// Error while parsing an expression, at the given byte offset
+    ParseTree {
+        /// Byte offset into the parsed string
+        pos: usize,
+        /// A short snippet of the string starting at `pos`
+        context: String,
+        /// The underlying parse error
+        err: Box<Error>,
+    },
     /// Script size too large
     ScriptSizeTooLarge,
+    /// Too many stack elements during the execution of a satisfaction
+    StackSizeTooLarge(usize),
     /// Anything but c:pk(key) (P2PK), c:pk_h(key) (P2PKH), and thresh_m(k,...)
     /// up to n=3 is invalid by standardness (bare)
     NonStandardBareScript,
@@ -385,7 +414,8 @@ const MAX_SCRIPT_SIZE: u32 = 10000;
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::InvalidOpcode(op) => write!(f, "invalid opcode {}", op),
+            Error::UnimplementedOpcode(op) => write!(f, "unimplemented opcode {}", op),
+            Error::DisabledOpcode(op) => write!(f, "disabled opcode {}", op),
             Error::NonMinimalVerify(ref tok) => write!(f, "{} VERIFY", tok),
             Error::InvalidPush(ref push) => write!(f, "invalid push {:?}", push), // TODO hexify this
             Error::Script(ref e) => fmt::Display::fmt(e, f),
@@ -419,22 +449,31 @@ impl fmt::Display for Error {
             Error::BadPubkey(ref e) => fmt::Display::fmt(e, f),
             Error::TypeCheck(ref e) => write!(f, "typecheck: {}", e),
             Error::BadDescriptor(ref e) => write!(f, "Invalid descriptor: {}", e),
+            Error::InvalidNesting {
+                ref outer,
+                ref inner,
+            } => write!(f, "cannot nest {} inside {}", inner, outer),
             Error::Secp(ref e) => fmt::Display::fmt(e, f),
+            Error::BlindError(ref s) => write!(f, "blinding failed: {}", s),
             Error::ContextError(ref e) => fmt::Display::fmt(e, f),
             #[cfg(feature = "compiler")]
             Error::CompilerError(ref e) => fmt::Display::fmt(e, f),
             Error::PolicyError(ref e) => fmt::Display::fmt(e, f),
             Error::LiftError(ref e) => fmt::Display::fmt(e, f),
-            Error::MaxRecursiveDepthExceeded => write!(
-                f,
-                "Recursive depth over {} not permitted",
-                MAX_RECURSION_DEPTH
-            ),
+            Error::MaxRecursiveDepthExceeded => f.write_str("Recursive depth limit exceeded"),
+            Error::ParseTree {
+                pos,
+                ref context,
+                ref err,
+            } => write!(f, "{} at position {} (near '{}')", err, pos, context),
             Error::ScriptSizeTooLarge => write!(
                 f,
                 "Standardness rules imply bitcoin than {} bytes",
                 MAX_SCRIPT_SIZE
             ),
+            Error::StackSizeTooLarge(n) => {
+                write!(f, "satisfaction may need up to {} stack elements", n)
+            }
             Error::NonStandardBareScript => write!(
                 f,
                 "Anything but c:pk(key) (P2PK), c:pk_h(key) (P2PKH), and thresh_m(k,...) \
@@ -18,7 +18,9 @@
 //!
 
 use bitcoin::PublicKey;
-use elements::{opcodes, script};
+use elements::encode::{deserialize, serialize};
+use elements::hashes::hex::ToHex;
+use elements::{confidential, opcodes, script};
 
 use std::fmt;
 
@@ -66,10 +68,23 @@ pub enum Token {
     Hash20([u8; 20]),
     Hash32([u8; 32]),
     Pubkey(PublicKey),
-    Push(Vec<u8>),        // Num or a
-    PickPush4(u32),       // Pick followed by a 4 byte push
-    PickPush32([u8; 32]), // Pick followed by a 32 byte push
-    PickPush(Vec<u8>),    // Pick followed by a push
+    Push(Vec<u8>),                        // Num or a
+    PickPush4(u32),                       // Pick followed by a 4 byte push
+    PickPush32([u8; 32]),                 // Pick followed by a 32 byte push
+    PickPush(Vec<u8>),                    // Pick followed by a push
+    AssetCommitment(confidential::Asset), // Asset compared with EQUAL
+    ValueBlob(confidential::Value),       // Value compared with EQUAL
+    Return(Vec<u8>),                      // OP_RETURN and its data push, always last
+}
+
+impl Token {
+    /// Whether no script is executed after this token, i.e. for `OP_RETURN`
+    pub fn is_terminating(&self) -> bool {
+        match *self {
+            Token::Return(..) => true,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for Token {
@@ -89,6 +104,9 @@ impl fmt::Display for Token {
                 Ok(())
             }
             Token::Pubkey(pk) => write!(f, "{}", pk),
+            Token::AssetCommitment(ref asset) => f.write_str(&serialize(asset).to_hex()),
+            Token::ValueBlob(ref value) => f.write_str(&serialize(value).to_hex()),
+            Token::Return(ref data) => write!(f, "Return({})", data.to_hex()),
             x => write!(f, "{:?}", x),
         }
     }
@@ -155,6 +173,84 @@ impl Iterator for TokenIter {
     }
 }
 
+/// Composite token made up of a sequence of [`Token`]s commonly
+/// used by covenant scripts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CovenantToken {
+    /// `DEPTH <n> SUB PICK`: copy the sighash component located `n` items
+    /// above the bottom of the stack
+    DepthPick(u32),
+    /// `SIZE <n> EQUALVERIFY`: check that the top stack element is `n`
+    /// bytes long, as done before hashing a preimage
+    SizeEqualVerify(u32),
+    /// A token which is not part of any recognized sequence
+    Token(Token),
+}
+
+/// Iterator over a [`TokenIter`] which groups common covenant
+/// sub-sequences into [`CovenantToken`]s.
+/// Like [`TokenIter`], the tokens are read backward.
+#[derive(Debug, Clone)]
+pub struct CovenantTokenIter(TokenIter);
+
+impl CovenantTokenIter {
+    /// Create a new CovenantTokenIter
+    pub fn new(tokens: TokenIter) -> CovenantTokenIter {
+        CovenantTokenIter(tokens)
+    }
+
+    /// Access the underlying raw tokens
+    pub fn as_inner(&self) -> &TokenIter {
+        &self.0
+    }
+
+    /// Get back the underlying raw tokens
+    pub fn into_inner(self) -> TokenIter {
+        self.0
+    }
+}
+
+impl Iterator for CovenantTokenIter {
+    type Item = CovenantToken;
+
+    fn next(&mut self) -> Option<CovenantToken> {
+        let grouped = match self.0.peek_slice(4) {
+            Some(&[Token::Depth, Token::Num(n), Token::Sub, Token::Pick]) => {
+                Some(CovenantToken::DepthPick(n))
+            }
+            Some(&[Token::Size, Token::Num(n), Token::Equal, Token::Verify]) => {
+                Some(CovenantToken::SizeEqualVerify(n))
+            }
+            _ => None,
+        };
+        if grouped.is_some() {
+            self.0.advance(4).expect("Size checked previously");
+            return grouped;
+        }
+        // A pick followed by a push is lexed as a single token. Split it
+        // so that the pick can be grouped with the preceding tokens.
+        let push = match self.0.peek() {
+            Some(&Token::PickPush4(n)) => Some(vec![
+                n as u8,
+                (n >> 8) as u8,
+                (n >> 16) as u8,
+                (n >> 24) as u8,
+            ]),
+            Some(&Token::PickPush32(ref x)) => Some(x.to_vec()),
+            Some(&Token::PickPush(ref x)) => Some(x.clone()),
+            _ => None,
+        };
+        match push {
+            Some(push) => {
+                self.0.next();
+                self.0.un_next(Token::Pick);
+                Some(CovenantToken::Token(Token::Push(push)))
+            }
+            None => self.0.next().map(CovenantToken::Token),
+        }
+    }
+}
+
 /// Tokenize a script
 pub fn lex(script: &script::Script) -> Result<Vec<Token>, Error> {
     let mut ret = Vec::with_capacity(script.len());
@@ -169,6 +265,8 @@ pub fn lex(script: &script::Script) -> Result<Vec<Token>, Error> {
             Token::Hash20(x) => Token::Push(x.to_vec()),
             Token::Hash32(x) => Token::Push(x.to_vec()),
             Token::Pubkey(pk) => Token::Push(pk.to_bytes()),
+            Token::AssetCommitment(x) => Token::Push(serialize(x)),
+            Token::ValueBlob(x) => Token::Push(serialize(x)),
             Token::Num(k) => Token::Push(build_scriptint(*k as i64)),
             _x => return Ok(()), // no change required
         };
@@ -176,7 +274,33 @@ pub fn lex(script: &script::Script) -> Result<Vec<Token>, Error> {
         Ok(())
     }
 
-    for ins in script.instructions_minimal() {
+    // Explicit (prefix 1) and confidential asset and value commitments
+    // are pinned by pushing them and comparing with EQUAL. Reclassify
+    // such a push, which was lexed as an opaque `Push`, when we see
+    // the comparison.
+    fn process_candidate_commitment(ret: &mut Vec<Token>) {
+        let token = match ret.last() {
+            Some(&Token::Push(ref x)) if x.len() == 33 && is_asset_prefix(x[0]) => {
+                match deserialize(x) {
+                    Ok(asset) => Token::AssetCommitment(asset),
+                    Err(_) => return,
+                }
+            }
+            Some(&Token::Push(ref x))
+                if (x.len() == 33 && is_value_prefix(x[0])) || (x.len() == 9 && x[0] == 1) =>
+            {
+                match deserialize(x) {
+                    Ok(value) => Token::ValueBlob(value),
+                    Err(_) => return,
+                }
+            }
+            _ => return,
+        };
+        *ret.last_mut().unwrap() = token;
+    }
+
+    let mut instructions = script.instructions_minimal();
+    while let Some(ins) = instructions.next() {
         match ins.map_err(Error::Script)? {
             script::Instruction::Op(opcodes::all::OP_BOOLAND) => {
                 ret.push(Token::BoolAnd);
@@ -185,9 +309,11 @@ pub fn lex(script: &script::Script) -> Result<Vec<Token>, Error> {
                 ret.push(Token::BoolOr);
             }
             script::Instruction::Op(opcodes::all::OP_EQUAL) => {
+                process_candidate_commitment(&mut ret);
                 ret.push(Token::Equal);
             }
             script::Instruction::Op(opcodes::all::OP_EQUALVERIFY) => {
+                process_candidate_commitment(&mut ret);
                 ret.push(Token::Equal);
                 ret.push(Token::Verify);
             }
@@ -338,6 +464,11 @@ pub fn lex(script: &script::Script) -> Result<Vec<Token>, Error> {
                             x.copy_from_slice(bytes);
                             ret.push(Token::Hash32(x));
                         }
+                        // Asset and value commitments are not valid keys, keep
+                        // them as pushes. See [process_candidate_commitment]
+                        33 if is_asset_prefix(bytes[0]) || is_value_prefix(bytes[0]) => {
+                            ret.push(Token::Push(bytes.to_owned()));
+                        }
                         33 | 65 => {
                             ret.push(Token::Pubkey(
                                 PublicKey::from_slice(bytes).map_err(Error::BadPubkey)?,
@@ -411,8 +542,196 @@ pub fn lex(script: &script::Script) -> Result<Vec<Token>, Error> {
             script::Instruction::Op(opcodes::all::OP_PUSHNUM_16) => {
                 ret.push(Token::Num(16));
             }
-            script::Instruction::Op(op) => return Err(Error::InvalidOpcode(op)),
+            // Execution fails at OP_RETURN, so the script can only carry
+            // data after it. Accept a single data push, as in standard
+            // `nulldata` outputs.
+            script::Instruction::Op(opcodes::all::OP_RETURN) => {
+                let data = match instructions.next() {
+                    None => vec![],
+                    Some(Ok(script::Instruction::PushBytes(bytes))) => bytes.to_owned(),
+                    Some(Ok(script::Instruction::Op(op))) => {
+                        return Err(Error::Trailing(format!("{:?}", op)))
+                    }
+                    Some(Err(e)) => return Err(Error::Script(e)),
+                };
+                if let Some(ins) = instructions.next() {
+                    return Err(Error::Trailing(format!("{:?}", ins)));
+                }
+                ret.push(Token::Return(data));
+            }
+            script::Instruction::Op(op) if is_disabled_opcode(op) => {
+                return Err(Error::DisabledOpcode(op))
+            }
+            script::Instruction::Op(op) => return Err(Error::UnimplementedOpcode(op)),
         };
     }
     Ok(ret)
 }
+
+/// Whether `op` is disabled in Elements script, so that any script
+/// containing it is invalid. Elements re-enables most of the opcodes
+/// disabled in bitcoin, such as `OP_CAT` and `OP_LEFT`, but not these.
+fn is_disabled_opcode(op: opcodes::All) -> bool {
+    op == opcodes::all::OP_2MUL
+        || op == opcodes::all::OP_2DIV
+        || op == opcodes::all::OP_MUL
+        || op == opcodes::all::OP_DIV
+        || op == opcodes::all::OP_MOD
+}
+
+/// Whether `prefix` is the first byte of an explicit (1) or
+/// confidential (10 or 11) asset
+fn is_asset_prefix(prefix: u8) -> bool {
+    prefix == 1 || prefix == 10 || prefix == 11
+}
+
+/// Whether `prefix` is the first byte of a 33-byte confidential
+/// value commitment
+fn is_value_prefix(prefix: u8) -> bool {
+    prefix == 8 || prefix == 9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::hashes::{sha256, Hash};
+    use elements::AssetId;
+
+    fn cov_tokens(script: &script::Script) -> Vec<CovenantToken> {
+        CovenantTokenIter::new(TokenIter::new(lex(script).unwrap())).collect()
+    }
+
+    #[test]
+    fn group_sighash_pick() {
+        let script = script::Builder::new()
+            .push_opcode(opcodes::all::OP_DEPTH)
+            .push_int(12)
+            .push_opcode(opcodes::all::OP_SUB)
+            .push_opcode(opcodes::all::OP_PICK)
+            .push_slice(&[2, 0, 0, 0])
+            .push_opcode(opcodes::all::OP_EQUAL)
+            .into_script();
+        assert_eq!(
+            cov_tokens(&script),
+            vec![
+                CovenantToken::Token(Token::Equal),
+                CovenantToken::Token(Token::Push(vec![2, 0, 0, 0])),
+                CovenantToken::DepthPick(12),
+            ]
+        );
+    }
+
+    #[test]
+    fn group_preimage_size_check() {
+        let hash = sha256::Hash::hash(&[0xab; 32]);
+        let script = script::Builder::new()
+            .push_opcode(opcodes::all::OP_SIZE)
+            .push_int(32)
+            .push_opcode(opcodes::all::OP_EQUALVERIFY)
+            .push_opcode(opcodes::all::OP_SHA256)
+            .push_slice(&hash[..])
+            .push_opcode(opcodes::all::OP_EQUAL)
+            .into_script();
+        assert_eq!(
+            cov_tokens(&script),
+            vec![
+                CovenantToken::Token(Token::Equal),
+                CovenantToken::Token(Token::Hash32(hash.into_inner())),
+                CovenantToken::Token(Token::Sha256),
+                CovenantToken::SizeEqualVerify(32),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_asset_and_value_commitments() {
+        let mut asset = [0x22; 33];
+        asset[0] = 1;
+        let script = script::Builder::new()
+            .push_slice(&asset)
+            .push_opcode(opcodes::all::OP_EQUAL)
+            .into_script();
+        let asset_id = AssetId::from_slice(&[0x22; 32]).unwrap();
+        assert_eq!(
+            lex(&script).unwrap(),
+            vec![
+                Token::AssetCommitment(confidential::Asset::Explicit(asset_id)),
+                Token::Equal
+            ]
+        );
+
+        let value = [1, 0, 0, 0, 0, 0, 0, 0x03, 0xe8];
+        let script = script::Builder::new()
+            .push_slice(&value)
+            .push_opcode(opcodes::all::OP_EQUALVERIFY)
+            .into_script();
+        assert_eq!(
+            lex(&script).unwrap(),
+            vec![
+                Token::ValueBlob(confidential::Value::Explicit(1000)),
+                Token::Equal,
+                Token::Verify
+            ]
+        );
+
+        // Without a comparison the commitment is an opaque push
+        let script = script::Builder::new()
+            .push_slice(&asset)
+            .push_opcode(opcodes::all::OP_DROP)
+            .into_script();
+        assert_eq!(
+            lex(&script).unwrap(),
+            vec![Token::Push(asset.to_vec()), Token::Drop]
+        );
+    }
+
+    #[test]
+    fn disabled_opcodes() {
+        let script = script::Builder::new()
+            .push_int(2)
+            .push_int(3)
+            .push_opcode(opcodes::all::OP_MUL)
+            .into_script();
+        match lex(&script) {
+            Err(Error::DisabledOpcode(op)) => assert_eq!(op, opcodes::all::OP_MUL),
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        // Valid in Elements, but not used by any fragment
+        let script = script::Builder::new()
+            .push_int(2)
+            .push_int(3)
+            .push_int(4)
+            .push_opcode(opcodes::all::OP_ROT)
+            .into_script();
+        match lex(&script) {
+            Err(Error::UnimplementedOpcode(op)) => assert_eq!(op, opcodes::all::OP_ROT),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn lex_return() {
+        let script = script::Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .push_slice(&[0xab; 40])
+            .into_script();
+        let tokens = lex(&script).unwrap();
+        assert_eq!(tokens, vec![Token::Return(vec![0xab; 40])]);
+        assert!(tokens[0].is_terminating());
+
+        // A bare OP_RETURN carries no data
+        let script = script::Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .into_script();
+        assert_eq!(lex(&script).unwrap(), vec![Token::Return(vec![])]);
+
+        // Only a single data push may follow
+        let script = script::Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .push_slice(&[0xab; 40])
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script();
+        assert!(lex(&script).is_err());
+    }
+}
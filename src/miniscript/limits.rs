@@ -8,6 +8,9 @@ pub const MAX_OPS_PER_SCRIPT: usize = 201;
 /// Maximum p2wsh initial stack items
 // https://github.com/bitcoin/bitcoin/blob/875e1ccc9fe01e026e564dfd39a64d9a4b332a89/src/policy/policy.h#L40
 pub const MAX_STANDARD_P2WSH_STACK_ITEMS: usize = 100;
+/// Maximum number of elements on the stack and altstack combined allowed
+/// by consensus rules during script execution
+pub const MAX_STACK_SIZE: usize = 1000;
 /// Maximum script size allowed by consensus rules
 // https://github.com/bitcoin/bitcoin/blob/42b66a6b814bca130a9ccf0a3f747cf33d628232/src/script/script.h#L32
 pub const MAX_SCRIPT_SIZE: usize = 10_000;
@@ -34,6 +37,12 @@ pub const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
 // https://github.com/bitcoin/bips/blob/master/bip-0112.mediawiki
 pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
 
+/// Mask extracting the relative locktime value from a sequence number
+/* If nSequence encodes a relative lock-time, this mask is
+ * applied to extract that lock-time from the sequence field. */
+// https://github.com/bitcoin/bips/blob/master/bip-0068.mediawiki
+pub const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000ffff;
+
 /// Maximum script element size allowed by consensus rules
 // https://github.com/bitcoin/bitcoin/blob/42b66a6b814bca130a9ccf0a3f747cf33d628232/src/script/script.h#L23
 pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
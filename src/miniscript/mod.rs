@@ -204,6 +204,26 @@ where
         self.node.encode(script::Builder::new()).into_script()
     }
 
+    /// Encode as a Bitcoin script, first checking that the miniscript is a
+    /// valid top-level fragment for its script context
+    ///
+    /// Unlike [`Miniscript::encode`], this errors rather than producing a
+    /// script which would not parse back, e.g. for a miniscript built with
+    /// [`Miniscript::from_ast`] containing an uncompressed key in a segwit
+    /// context.
+    pub fn to_script(&self) -> Result<script::Script, Error>
+    where
+        Pk: ToPublicKey,
+    {
+        for ms in self.iter() {
+            Ctx::check_global_validity(ms)?;
+        }
+        if self.ty.corr.base != types::Base::B {
+            return Err(Error::NonTopLevel(format!("{:?}", self)));
+        }
+        Ok(self.encode())
+    }
+
     /// Size, in bytes of the script-pubkey. If this Miniscript is used outside
     /// of segwit (e.g. in a bare or P2SH descriptor), this quantity should be
     /// multiplied by 4 to compute the weight.
@@ -246,6 +266,171 @@ impl<Pk: MiniscriptKey, Ctx: ScriptContext, Ext: Extension<Pk>> Miniscript<Pk, C
     pub fn max_satisfaction_size(&self) -> Result<usize, Error> {
         Ctx::max_satisfaction_size::<Pk, Ctx, Ext>(self).ok_or(Error::ImpossibleSatisfaction)
     }
+
+    /// Upper bound on the number of elements which executing the script
+    /// places on the stack (and altstack) on top of its witness. Added to
+    /// the number of witness elements this bounds the stack depth reached
+    /// during execution.
+    pub fn max_exec_stack_extra(&self) -> usize
+    where
+        Pk: ToPublicKey,
+    {
+        match self.node {
+            Terminal::True
+            | Terminal::False
+            | Terminal::PkK(..)
+            | Terminal::After(..)
+            | Terminal::Older(..) => 1,
+            // The key (resp. preimage size) is duplicated before the hash
+            // is pushed
+            Terminal::PkH(..)
+            | Terminal::Sha256(..)
+            | Terminal::Hash256(..)
+            | Terminal::Ripemd160(..)
+            | Terminal::Hash160(..) => 2,
+            Terminal::Alt(ref sub)
+            | Terminal::Swap(ref sub)
+            | Terminal::Check(ref sub)
+            | Terminal::Verify(ref sub)
+            | Terminal::ZeroNotEqual(ref sub) => sub.max_exec_stack_extra(),
+            Terminal::DupIf(ref sub) | Terminal::NonZero(ref sub) => {
+                cmp::max(1, sub.max_exec_stack_extra())
+            }
+            Terminal::AndV(ref l, ref r)
+            | Terminal::OrC(ref l, ref r)
+            | Terminal::OrI(ref l, ref r) => {
+                cmp::max(l.max_exec_stack_extra(), r.max_exec_stack_extra())
+            }
+            Terminal::AndB(ref l, ref r) | Terminal::OrB(ref l, ref r) => {
+                cmp::max(l.max_exec_stack_extra(), 1 + r.max_exec_stack_extra())
+            }
+            // The result of the left branch is duplicated by `IFDUP`
+            Terminal::OrD(ref l, ref r) => cmp::max(
+                cmp::max(l.max_exec_stack_extra(), 2),
+                r.max_exec_stack_extra(),
+            ),
+            Terminal::AndOr(ref a, ref b, ref c) => cmp::max(
+                a.max_exec_stack_extra(),
+                cmp::max(b.max_exec_stack_extra(), c.max_exec_stack_extra()),
+            ),
+            // Each result is kept below the following subexpressions, and
+            // the sum is compared against `k`
+            Terminal::Thresh(_, ref subs) => subs
+                .iter()
+                .enumerate()
+                .map(|(i, sub)| cmp::min(i, 1) + sub.max_exec_stack_extra())
+                .fold(2, cmp::max),
+            Terminal::Multi(_, ref keys) => keys.len() + 2,
+            // Each opcode of an extension pushes at most one element
+            Terminal::Ext(ref e) => e
+                .push_to_builder(script::Builder::new())
+                .into_script()
+                .instructions()
+                .count(),
+        }
+    }
+}
+
+#[cfg(feature = "use-serde")]
+impl<Pk: MiniscriptKey, Ctx: ScriptContext, Ext: Extension<Pk>> Miniscript<Pk, Ctx, Ext> {
+    /// Describes the structure of the miniscript as a JSON object
+    ///
+    /// Every node of the AST is an object with a `fragment` name and its
+    /// base `type` (`B`, `V`, `K` or `W`), along with, where applicable, the
+    /// threshold `k`, the `keys`, the timelock `value`, the `hash`, and the
+    /// subexpressions as `args`. Wrappers and aliases are not collapsed, so
+    /// `pk(A)` is a `c` node with a `pk_k` argument.
+    pub fn to_json_tree(&self) -> ::serde_json::Value {
+        use elements::hashes::Hash;
+        use serde_json::{Map, Value};
+
+        let mut node = Map::new();
+        let (fragment, args): (String, Vec<&Arc<Miniscript<Pk, Ctx, Ext>>>) = {
+            let mut insert = |name: &str, value: Value| {
+                node.insert(name.to_owned(), value);
+            };
+            match self.node {
+                Terminal::True => ("1".to_owned(), vec![]),
+                Terminal::False => ("0".to_owned(), vec![]),
+                Terminal::PkK(ref pk) => {
+                    insert("keys", Value::from(vec![pk.to_string()]));
+                    ("pk_k".to_owned(), vec![])
+                }
+                Terminal::PkH(ref pkh) => {
+                    insert("keys", Value::from(vec![pkh.to_string()]));
+                    ("pk_h".to_owned(), vec![])
+                }
+                Terminal::After(t) => {
+                    insert("value", Value::from(t));
+                    ("after".to_owned(), vec![])
+                }
+                Terminal::Older(t) => {
+                    insert("value", Value::from(t));
+                    ("older".to_owned(), vec![])
+                }
+                Terminal::Sha256(h) => {
+                    insert("hash", Value::from(h.to_string()));
+                    ("sha256".to_owned(), vec![])
+                }
+                Terminal::Hash256(h) => {
+                    // Displayed in the same byte order as in the descriptor string
+                    let mut x = h.into_inner();
+                    x.reverse();
+                    let h = ::elements::hashes::sha256d::Hash::from_inner(x);
+                    insert("hash", Value::from(h.to_string()));
+                    ("hash256".to_owned(), vec![])
+                }
+                Terminal::Ripemd160(h) => {
+                    insert("hash", Value::from(h.to_string()));
+                    ("ripemd160".to_owned(), vec![])
+                }
+                Terminal::Hash160(h) => {
+                    insert("hash", Value::from(h.to_string()));
+                    ("hash160".to_owned(), vec![])
+                }
+                Terminal::Ext(ref e) => {
+                    let value = e.to_string();
+                    let fragment = value.split('(').next().unwrap_or("").to_owned();
+                    insert("value", Value::from(value));
+                    (fragment, vec![])
+                }
+                Terminal::Alt(ref sub) => ("a".to_owned(), vec![sub]),
+                Terminal::Swap(ref sub) => ("s".to_owned(), vec![sub]),
+                Terminal::Check(ref sub) => ("c".to_owned(), vec![sub]),
+                Terminal::DupIf(ref sub) => ("d".to_owned(), vec![sub]),
+                Terminal::Verify(ref sub) => ("v".to_owned(), vec![sub]),
+                Terminal::NonZero(ref sub) => ("j".to_owned(), vec![sub]),
+                Terminal::ZeroNotEqual(ref sub) => ("n".to_owned(), vec![sub]),
+                Terminal::AndV(ref l, ref r) => ("and_v".to_owned(), vec![l, r]),
+                Terminal::AndB(ref l, ref r) => ("and_b".to_owned(), vec![l, r]),
+                Terminal::AndOr(ref a, ref b, ref c) => ("andor".to_owned(), vec![a, b, c]),
+                Terminal::OrB(ref l, ref r) => ("or_b".to_owned(), vec![l, r]),
+                Terminal::OrD(ref l, ref r) => ("or_d".to_owned(), vec![l, r]),
+                Terminal::OrC(ref l, ref r) => ("or_c".to_owned(), vec![l, r]),
+                Terminal::OrI(ref l, ref r) => ("or_i".to_owned(), vec![l, r]),
+                Terminal::Thresh(k, ref subs) => {
+                    insert("k", Value::from(k));
+                    ("thresh".to_owned(), subs.iter().collect())
+                }
+                Terminal::Multi(k, ref keys) => {
+                    insert("k", Value::from(k));
+                    let keys: Vec<String> = keys.iter().map(|pk| pk.to_string()).collect();
+                    insert("keys", Value::from(keys));
+                    ("multi".to_owned(), vec![])
+                }
+            }
+        };
+        node.insert("fragment".to_owned(), Value::from(fragment));
+        node.insert(
+            "type".to_owned(),
+            Value::from(format!("{:?}", self.ty.corr.base)),
+        );
+        if !args.is_empty() {
+            let args: Vec<Value> = args.iter().map(|sub| sub.to_json_tree()).collect();
+            node.insert("args".to_owned(), Value::from(args));
+        }
+        Value::Object(node)
+    }
 }
 
 impl<Pk: MiniscriptKey, Ctx: ScriptContext, Ext: Extension<Pk>> ForEachKey<Pk>
@@ -387,6 +572,25 @@ impl<Pk: MiniscriptKey, Ctx: ScriptContext, Ext: Extension<Pk>> Miniscript<Pk, C
             }
         }
     }
+
+    /// Attempt to produce the canonical dissatisfying witness for the
+    /// witness script represented by the parse tree, i.e. a witness under
+    /// which the script evaluates to false. Errs for fragments which
+    /// cannot be dissatisfied, such as `v:` wrappers or timelocks.
+    pub fn dissatisfy<S: satisfy::Satisfier<Pk>>(&self, satisfier: S) -> Result<Vec<Vec<u8>>, Error>
+    where
+        Pk: ToPublicKey,
+    {
+        match satisfy::Satisfaction::dissatisfy(&self.node, &satisfier, self.ty.mall.safe).stack {
+            satisfy::Witness::Stack(stack) => {
+                Ctx::check_witness::<Pk, Ctx, Ext>(&stack)?;
+                Ok(stack)
+            }
+            satisfy::Witness::Unavailable | satisfy::Witness::Impossible => {
+                Err(Error::CouldNotSatisfy)
+            }
+        }
+    }
 }
 
 impl<Pk, Ctx, Ext> expression::FromTree for Arc<Miniscript<Pk, Ctx, Ext>>
@@ -932,6 +1136,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn to_script() {
+        let pks = pubkeys(2);
+        let ms: Segwitv0Script = ms_str!("and_v(v:pk({}),pk({}))", pks[0], pks[1]);
+        let script = ms.to_script().unwrap();
+        assert_eq!(script, ms.encode());
+        assert_eq!(Segwitv0Script::parse(&script).unwrap(), ms);
+
+        // Fragments which are not top-level are rejected
+        let pk_k = Segwitv0Script::from_ast(Terminal::PkK(pks[0])).unwrap();
+        assert!(pk_k.to_script().is_err());
+
+        // As are keys which are not valid in the context
+        let mut uncompressed = pks[0];
+        uncompressed.compressed = false;
+        let pk = Segwitv0Script::from_ast(Terminal::Check(Arc::new(
+            Segwitv0Script::from_ast(Terminal::PkK(uncompressed)).unwrap(),
+        )))
+        .unwrap();
+        assert!(pk.to_script().is_err());
+    }
+
     #[test]
     fn deserialize() {
         // Most of these came from fuzzing, hence the increasing lengths
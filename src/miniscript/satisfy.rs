@@ -114,6 +114,16 @@ pub trait Satisfier<Pk: MiniscriptKey + ToPublicKey> {
         false
     }
 
+    /// Given the `Descriptor::id` of a legacy `sh` descriptor, look up a
+    /// complete scriptSig produced by a signer which does not expose its
+    /// individual signatures. The scriptSig is rejected unless the
+    /// interpreter finds that it satisfies the descriptor; since signatures
+    /// can only be verified against the spending transaction, callers should
+    /// still check the final transaction, e.g. with `pset::interpreter_check`.
+    fn lookup_complete_scriptsig(&self, _: &[u8; 32]) -> Option<Script> {
+        None
+    }
+
     /// Introspection Data for Covenant support
     /// #1 Version
     fn lookup_nversion(&self) -> Option<u32> {
@@ -275,6 +285,10 @@ impl<'a, Pk: MiniscriptKey + ToPublicKey, S: Satisfier<Pk>> Satisfier<Pk> for &'
         (**self).check_after(t)
     }
 
+    fn lookup_complete_scriptsig(&self, id: &[u8; 32]) -> Option<Script> {
+        (**self).lookup_complete_scriptsig(id)
+    }
+
     fn lookup_nversion(&self) -> Option<u32> {
         (**self).lookup_nversion()
     }
@@ -357,6 +371,10 @@ impl<'a, Pk: MiniscriptKey + ToPublicKey, S: Satisfier<Pk>> Satisfier<Pk> for &'
         (**self).check_after(t)
     }
 
+    fn lookup_complete_scriptsig(&self, id: &[u8; 32]) -> Option<Script> {
+        (**self).lookup_complete_scriptsig(id)
+    }
+
     fn lookup_nversion(&self) -> Option<u32> {
         (**self).lookup_nversion()
     }
@@ -506,6 +524,16 @@ macro_rules! impl_tuple_satisfier {
                 false
             }
 
+            fn lookup_complete_scriptsig(&self, id: &[u8; 32]) -> Option<Script> {
+                let &($(ref $ty,)*) = self;
+                $(
+                    if let Some(result) = $ty.lookup_complete_scriptsig(id) {
+                        return Some(result);
+                    }
+                )*
+                None
+            }
+
             fn lookup_nversion(&self) -> Option<u32> {
                 let &($(ref $ty,)*) = self;
                 $(
@@ -1362,6 +1390,27 @@ impl Satisfaction {
         )
     }
 
+    /// Produce a dissatisfaction
+    pub(super) fn dissatisfy<Pk, Ctx, Sat, Ext>(
+        term: &Terminal<Pk, Ctx, Ext>,
+        stfr: &Sat,
+        root_has_sig: bool,
+    ) -> Self
+    where
+        Pk: MiniscriptKey + ToPublicKey,
+        Ctx: ScriptContext,
+        Sat: Satisfier<Pk>,
+        Ext: Extension<Pk>,
+    {
+        Self::dissatisfy_helper(
+            term,
+            stfr,
+            root_has_sig,
+            &mut Satisfaction::minimum,
+            &mut Satisfaction::thresh,
+        )
+    }
+
     /// Produce a satisfaction(possibly malleable)
     pub(super) fn satisfy_mall<
         Pk: MiniscriptKey + ToPublicKey,
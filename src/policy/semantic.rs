@@ -14,6 +14,7 @@
 
 //! Abstract Policies
 
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::{fmt, str};
 
@@ -22,6 +23,7 @@ use elements::hashes::{hash160, ripemd160, sha256, sha256d};
 
 use super::concrete::PolicyError;
 use errstr;
+use miniscript::limits::{HEIGHT_TIME_THRESHOLD, SEQUENCE_LOCKTIME_TYPE_FLAG};
 use Error;
 use {expression, ForEach, ForEachKey, MiniscriptKey};
 
@@ -526,6 +528,83 @@ impl<Pk: MiniscriptKey> Policy<Pk> {
             }
         }
     }
+
+    /// Enumerate the distinct ways in which the policy can be satisfied.
+    /// Each returned path lists the keys which must sign together with the
+    /// timelocks and hash preimages it requires; satisfying any one path
+    /// satisfies the policy. The number of paths grows combinatorially
+    /// with the size of thresholds, so this is intended for small policies.
+    pub fn spend_paths(&self) -> Vec<SpendPath<Pk>> {
+        let mut ret: Vec<SpendPath<Pk>> = self
+            .real_spend_paths()
+            .into_iter()
+            .map(|mut leaves| {
+                leaves.sort();
+                leaves.dedup();
+                let mut path = SpendPath {
+                    keys: vec![],
+                    conditions: vec![],
+                };
+                for leaf in leaves {
+                    match leaf {
+                        Policy::KeyHash(pkh) => path.keys.push(pkh),
+                        x => path.conditions.push(x),
+                    }
+                }
+                path
+            })
+            .collect();
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+
+    /// Helper function to do the recursion in `spend_paths`.
+    fn real_spend_paths(&self) -> Vec<Vec<Policy<Pk>>> {
+        match *self {
+            Policy::Unsatisfiable => vec![],
+            Policy::Trivial => vec![vec![]],
+            Policy::Threshold(k, ref subs) => {
+                let sub_paths: Vec<_> = subs.iter().map(Policy::real_spend_paths).collect();
+                threshold_spend_paths(k, &sub_paths)
+            }
+            ref x => vec![vec![x.clone()]],
+        }
+    }
+}
+
+/// Combine the spend paths of the children of a `k`-of-n threshold, by
+/// picking every `k`-subset of children and every path of each child
+fn threshold_spend_paths<Pk: MiniscriptKey>(
+    k: usize,
+    sub_paths: &[Vec<Vec<Policy<Pk>>>],
+) -> Vec<Vec<Policy<Pk>>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    if sub_paths.len() < k {
+        return vec![];
+    }
+    let mut ret = vec![];
+    for tail in threshold_spend_paths(k - 1, &sub_paths[1..]) {
+        for head in &sub_paths[0] {
+            let mut path = head.clone();
+            path.extend(tail.iter().cloned());
+            ret.push(path);
+        }
+    }
+    ret.extend(threshold_spend_paths(k, &sub_paths[1..]));
+    ret
+}
+
+/// A single way of satisfying a semantic policy, as returned by
+/// [`Policy::spend_paths`]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct SpendPath<Pk: MiniscriptKey> {
+    /// Hashes of the keys which must all sign
+    pub keys: Vec<Pk::Hash>,
+    /// The timelocks and hash preimages which are also required
+    pub conditions: Vec<Policy<Pk>>,
 }
 
 impl<Pk: MiniscriptKey> Policy<Pk> {
@@ -543,6 +622,68 @@ impl<Pk: MiniscriptKey> Policy<Pk> {
             x => x,
         }
     }
+
+    /// Render the policy as an indented, human-readable tree
+    ///
+    /// Keys are displayed using the names in `key_names` if present, and
+    /// by their hash otherwise. Timelocks are described as block heights,
+    /// timestamps or block/second delays as appropriate.
+    pub fn pretty_print(&self, key_names: &HashMap<Pk::Hash, String>) -> String {
+        let mut ret = String::new();
+        self.pretty_print_helper(key_names, 0, &mut ret);
+        ret
+    }
+
+    fn pretty_print_helper(
+        &self,
+        key_names: &HashMap<Pk::Hash, String>,
+        indent: usize,
+        ret: &mut String,
+    ) {
+        for _ in 0..indent {
+            ret.push_str("  ");
+        }
+        match *self {
+            Policy::Unsatisfiable => ret.push_str("unsatisfiable"),
+            Policy::Trivial => ret.push_str("always satisfied"),
+            Policy::KeyHash(ref pkh) => match key_names.get(pkh) {
+                Some(name) => ret.push_str(&format!("signature by {}", name)),
+                None => ret.push_str(&format!("signature by key with hash {}", pkh)),
+            },
+            Policy::After(n) => {
+                if n < HEIGHT_TIME_THRESHOLD {
+                    ret.push_str(&format!("after block height {}", n))
+                } else {
+                    ret.push_str(&format!("after unix time {}", n))
+                }
+            }
+            Policy::Older(n) => {
+                if n & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+                    let secs = (n & 0xffff) * 512;
+                    ret.push_str(&format!("{} seconds after confirmation", secs))
+                } else {
+                    ret.push_str(&format!("{} blocks after confirmation", n))
+                }
+            }
+            Policy::Sha256(h) => ret.push_str(&format!("preimage of sha256 {}", h)),
+            Policy::Hash256(h) => ret.push_str(&format!("preimage of hash256 {}", h)),
+            Policy::Ripemd160(h) => ret.push_str(&format!("preimage of ripemd160 {}", h)),
+            Policy::Hash160(h) => ret.push_str(&format!("preimage of hash160 {}", h)),
+            Policy::Threshold(k, ref subs) => {
+                if k == subs.len() {
+                    ret.push_str("all of:");
+                } else if k == 1 {
+                    ret.push_str("any of:");
+                } else {
+                    ret.push_str(&format!("{} of {}:", k, subs.len()));
+                }
+                for sub in subs {
+                    ret.push('\n');
+                    sub.pretty_print_helper(key_names, indent + 1, ret);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -633,6 +774,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pretty_print() {
+        let policy =
+            StringPolicy::from_str("or(thresh(2,pkh(A),pkh(B),pkh(C)),and(after(100),pkh(D)))")
+                .unwrap();
+        let mut names = HashMap::new();
+        names.insert("A".to_owned(), "Alice".to_owned());
+        names.insert("B".to_owned(), "Bob".to_owned());
+        names.insert("C".to_owned(), "Carol".to_owned());
+        assert_eq!(
+            policy.pretty_print(&names),
+            "any of:\n\
+             \x20 2 of 3:\n\
+             \x20   signature by Alice\n\
+             \x20   signature by Bob\n\
+             \x20   signature by Carol\n\
+             \x20 all of:\n\
+             \x20   after block height 100\n\
+             \x20   signature by key with hash D"
+        );
+
+        let policy = StringPolicy::from_str("older(4194310)").unwrap();
+        assert_eq!(
+            policy.pretty_print(&HashMap::new()),
+            "3072 seconds after confirmation"
+        );
+    }
+
     #[test]
     fn entailment_liquid_test() {
         //liquid policy
@@ -0,0 +1,395 @@
+// Miniscript
+// Written in 2021 by elements-miniscript developers
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! # Transaction helpers
+//!
+//! Utilities for working with elements transactions that spend to
+//! or from descriptors. Elements transactions carry an explicit fee
+//! output (an output with an empty scriptPubKey) instead of an implicit
+//! fee as in bitcoin.
+//!
+
+use elements::issuance::ContractHash;
+use elements::secp256k1_zkp::rand::{CryptoRng, RngCore};
+use elements::secp256k1_zkp::{self, CommitmentSecrets, Secp256k1, Signing, Tweak};
+use elements::{confidential, AssetId, OutPoint, Script, Transaction, TxOut, TxOutSecrets};
+
+use Error;
+
+/// Create an explicit fee output paying `amount` of `asset`
+pub fn fee_output(asset: AssetId, amount: u64) -> TxOut {
+    TxOut {
+        asset: confidential::Asset::Explicit(asset),
+        value: confidential::Value::Explicit(amount),
+        nonce: confidential::Nonce::Null,
+        script_pubkey: Script::new(),
+        witness: Default::default(),
+    }
+}
+
+/// Additional helper methods on elements [`Transaction`]
+// Maintained as a separate trait because Transaction is defined upstream
+pub trait TransactionExt {
+    /// Compute the explicit fee paid in `asset` by this transaction.
+    /// This is the sum of values of all explicit outputs with an
+    /// empty scriptPubKey for the given asset. Outputs with confidential
+    /// values or assets are ignored.
+    fn explicit_fee(&self, asset: AssetId) -> u64;
+
+    /// Compute the effective fee-rate, in satoshis per virtual byte, of
+    /// the explicit fee paid in `asset`. The virtual size is computed from
+    /// the full weight of the transaction, including its rangeproofs and
+    /// surjection proofs, so this should be called on the final blinded
+    /// and signed transaction.
+    fn effective_feerate(&self, asset: AssetId) -> f64;
+}
+
+impl TransactionExt for Transaction {
+    fn explicit_fee(&self, asset: AssetId) -> u64 {
+        self.output
+            .iter()
+            .filter(|out| out.script_pubkey.is_empty())
+            .filter_map(|out| match (out.asset, out.value) {
+                (confidential::Asset::Explicit(a), confidential::Value::Explicit(v))
+                    if a == asset =>
+                {
+                    Some(v)
+                }
+                _ => None,
+            })
+            .sum()
+    }
+
+    fn effective_feerate(&self, asset: AssetId) -> f64 {
+        self.explicit_fee(asset) as f64 * 4.0 / self.get_weight() as f64
+    }
+}
+
+/// Blinding data for a single confidential input or output: the explicit
+/// value, the asset blinding factor and the value blinding factor
+pub type BlindingData = (u64, Tweak, Tweak);
+
+/// Compute the value blinding factor of the last output so that the
+/// transaction balances.
+///
+/// For the value commitments to balance, the sum of `value * abf + vbf` over
+/// all inputs must equal the same sum over all outputs. Given the blinding
+/// data of all `inputs`, of all `outputs` except the last one, and the value
+/// and asset blinding factor of the last output, this returns the value
+/// blinding factor the last output must use.
+pub fn last_value_blinding_factor<C: Signing>(
+    secp: &Secp256k1<C>,
+    inputs: &[BlindingData],
+    outputs: &[BlindingData],
+    last_value: u64,
+    last_abf: Tweak,
+) -> Tweak {
+    let secrets = |data: &[BlindingData]| -> Vec<_> {
+        data.iter()
+            .map(|&(value, abf, vbf)| CommitmentSecrets::new(value, vbf, abf))
+            .collect()
+    };
+    secp256k1_zkp::compute_adaptive_blinding_factor(
+        secp,
+        last_value,
+        last_abf,
+        &secrets(inputs),
+        &secrets(outputs),
+    )
+}
+
+/// Blind the outputs of a transaction which pay to blinded descriptors.
+///
+/// `output_blinders` holds, for each output of `tx`, the blinding public
+/// key of the descriptor it pays to (e.g. the `blinding_pubkey` of its
+/// address), or `None` if the output should stay explicit, like the fee
+/// output. The outputs to be blinded must have explicit values and assets;
+/// their commitments, rangeproofs and surjection proofs are filled in.
+/// `spent_utxo_secrets` are the values, assets and blinding factors of the
+/// outputs spent by the inputs of `tx`, in order. Errs if `output_blinders`
+/// does not have one entry per output.
+pub fn blind_transaction<R, C>(
+    tx: &mut Transaction,
+    output_blinders: &[Option<secp256k1_zkp::PublicKey>],
+    spent_utxo_secrets: &[TxOutSecrets],
+    rng: &mut R,
+    secp: &Secp256k1<C>,
+) -> Result<(), Error>
+where
+    R: RngCore + CryptoRng,
+    C: Signing,
+{
+    if output_blinders.len() != tx.output.len() {
+        return Err(Error::BlindError(format!(
+            "{} output blinders for {} outputs",
+            output_blinders.len(),
+            tx.output.len()
+        )));
+    }
+    // Outputs are marked for blinding by carrying the receiver's blinding
+    // key in place of the nonce
+    for (out, blinder) in tx.output.iter_mut().zip(output_blinders) {
+        if let Some(pk) = *blinder {
+            out.nonce = confidential::Nonce::Confidential(pk);
+        }
+    }
+    // The spent asset commitments are recomputed from their secrets; an
+    // explicit asset has a zero blinding factor and yields the same generator
+    let spent: Vec<_> = spent_utxo_secrets
+        .iter()
+        .map(|s| {
+            (
+                confidential::Asset::new_confidential(secp, s.asset, s.asset_bf),
+                s,
+            )
+        })
+        .collect();
+    tx.blind(rng, secp, &spent)
+        .map(|_| ())
+        .map_err(|e| Error::BlindError(e.to_string()))
+}
+
+/// Compute the id of the asset issued by an input spending `prevout`
+/// with the given issuance `contract_hash`. Issuances without a contract
+/// use the all-zeroes hash.
+pub fn compute_asset_id(prevout: OutPoint, contract_hash: ContractHash) -> AssetId {
+    AssetId::from_entropy(AssetId::generate_asset_entropy(prevout, contract_hash))
+}
+
+/// Compute the id of the reissuance token created alongside the asset of
+/// [`compute_asset_id`]. The token id depends on whether the issued
+/// amount is `confidential`.
+pub fn compute_reissuance_token_id(
+    prevout: OutPoint,
+    contract_hash: ContractHash,
+    confidential: bool,
+) -> AssetId {
+    let entropy = AssetId::generate_asset_entropy(prevout, contract_hash);
+    AssetId::reissuance_token_from_entropy(entropy, confidential)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::secp256k1_zkp::{Generator, PedersenCommitment, SecretKey, ZERO_TWEAK};
+    use elements::{self, OutPoint, TxIn};
+
+    const BTC_ASSET: [u8; 32] = [
+        0x23, 0x0f, 0x4f, 0x5d, 0x4b, 0x7c, 0x6f, 0xa8, 0x45, 0x80, 0x6e, 0xe4, 0xf6, 0x77, 0x13,
+        0x45, 0x9e, 0x1b, 0x69, 0xe8, 0xe6, 0x0f, 0xce, 0xe2, 0xe4, 0x94, 0x0c, 0x7a, 0x0d, 0x5d,
+        0xe1, 0xb2,
+    ];
+
+    /// A transaction with a single unsigned input spending to `output`
+    fn spend_to(output: Vec<TxOut>) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::default(),
+                script_sig: Script::new(),
+                sequence: 0xffffffff,
+                is_pegin: false,
+                has_issuance: false,
+                asset_issuance: elements::AssetIssuance::default(),
+                witness: elements::TxInWitness::default(),
+            }],
+            output: output,
+        }
+    }
+
+    #[test]
+    fn explicit_fee() {
+        let btc = AssetId::from_slice(&BTC_ASSET).unwrap();
+        let other = AssetId::from_slice(&[1; 32]).unwrap();
+        let tx = spend_to(vec![
+            TxOut {
+                script_pubkey: Script::from(vec![0x51]),
+                ..fee_output(btc, 99_500)
+            },
+            fee_output(btc, 500),
+            fee_output(other, 1_000),
+        ]);
+        assert!(tx.output[1].script_pubkey.is_empty());
+        assert_eq!(tx.explicit_fee(btc), 500);
+        assert_eq!(tx.explicit_fee(other), 1_000);
+        assert_eq!(tx.explicit_fee(AssetId::from_slice(&[2; 32]).unwrap()), 0);
+    }
+
+    #[test]
+    fn effective_feerate() {
+        let btc = AssetId::from_slice(&BTC_ASSET).unwrap();
+        let mut tx = spend_to(vec![
+            TxOut {
+                script_pubkey: Script::from(vec![0x51]),
+                ..fee_output(btc, 99_500)
+            },
+            fee_output(btc, 500),
+        ]);
+        // Without witnesses the virtual size is the serialized size
+        let size = elements::encode::serialize(&tx).len();
+        assert_eq!(tx.get_weight(), 4 * size);
+        assert_eq!(tx.effective_feerate(btc), 500.0 / size as f64);
+        assert_eq!(
+            tx.effective_feerate(AssetId::from_slice(&[1; 32]).unwrap()),
+            0.0
+        );
+
+        // Witness data is discounted
+        let weight = tx.get_weight();
+        tx.input[0].witness.script_witness = vec![vec![0; 71], vec![0; 33]];
+        assert!(tx.get_weight() > weight);
+        assert!(tx.get_weight() < weight + 4 * 106);
+        assert!(tx.effective_feerate(btc) < 500.0 / size as f64);
+        assert_eq!(tx.effective_feerate(btc), 2000.0 / tx.get_weight() as f64);
+    }
+
+    #[test]
+    fn blind_transaction() {
+        use elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+        use elements::secp256k1_zkp::rand::thread_rng;
+        use std::str::FromStr;
+        use {bitcoin, DescriptorTrait};
+
+        let secp = Secp256k1::new();
+        let btc = AssetId::from_slice(&BTC_ASSET).unwrap();
+        let view_keys: Vec<SecretKey> = (1..3u8)
+            .map(|i| SecretKey::from_slice(&[i; 32]).unwrap())
+            .collect();
+        let descs: Vec<_> = view_keys
+            .iter()
+            .map(|sk| {
+                let blinder = secp256k1_zkp::PublicKey::from_secret_key(&secp, sk);
+                ::descriptor::Blinded::<bitcoin::PublicKey>::from_str(&format!(
+                    "blinded({},elwpkh(03a0434d9e47f3c86235477c7b1ae6ae5d3442d49b1943c2b752a68e2a47e247c7))",
+                    blinder
+                ))
+                .unwrap()
+            })
+            .collect();
+
+        let mut output: Vec<TxOut> = descs
+            .iter()
+            .zip(&[60_000, 39_500])
+            .map(|(desc, &value)| TxOut {
+                script_pubkey: desc.script_pubkey(),
+                ..fee_output(btc, value)
+            })
+            .collect();
+        output.push(fee_output(btc, 500));
+        let mut tx = spend_to(output);
+        let mut blinders: Vec<_> = descs
+            .iter()
+            .map(|desc| {
+                desc.address(&elements::AddressParams::ELEMENTS)
+                    .unwrap()
+                    .blinding_pubkey
+            })
+            .collect();
+        blinders.push(None);
+        let spent = [TxOutSecrets::new(
+            btc,
+            AssetBlindingFactor::zero(),
+            100_000,
+            ValueBlindingFactor::zero(),
+        )];
+
+        // Every output needs an entry, even if it stays explicit
+        match super::blind_transaction(&mut tx, &blinders[..2], &spent, &mut thread_rng(), &secp) {
+            Err(Error::BlindError(..)) => {}
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert!(tx.output.iter().all(|out| !out.nonce.is_confidential()));
+
+        super::blind_transaction(&mut tx, &blinders, &spent, &mut thread_rng(), &secp).unwrap();
+
+        for (i, &value) in [60_000, 39_500].iter().enumerate() {
+            let out = &tx.output[i];
+            assert!(out.value.is_confidential());
+            assert!(out.asset.is_confidential());
+            let secrets = out.unblind(&secp, view_keys[i]).unwrap();
+            assert_eq!(secrets.value, value);
+            assert_eq!(secrets.asset, btc);
+            // The other output's view key does not unblind it
+            assert!(out.unblind(&secp, view_keys[1 - i]).is_err());
+        }
+        // The fee output stays explicit
+        assert_eq!(tx.output[2], fee_output(btc, 500));
+        assert_eq!(tx.explicit_fee(btc), 500);
+    }
+
+    #[test]
+    fn last_value_blinding_factor() {
+        let secp = Secp256k1::new();
+        let tweak = |b: u8| Tweak::from_slice(&[b; 32]).unwrap();
+        let tag = AssetId::from_slice(&BTC_ASSET).unwrap().into_tag();
+        let commit = |&(value, abf, vbf): &BlindingData| {
+            let gen = Generator::new_blinded(&secp, tag, abf);
+            PedersenCommitment::new(&secp, value, vbf, gen)
+        };
+
+        // 1-in 2-out with explicit assets: the value blinders must cancel
+        let input = (100_000, ZERO_TWEAK, tweak(1));
+        let out1 = (60_000, ZERO_TWEAK, tweak(1));
+        let vbf = super::last_value_blinding_factor(&secp, &[input], &[out1], 40_000, ZERO_TWEAK);
+        assert_eq!(vbf, ZERO_TWEAK);
+
+        // 2-in 3-out fully confidential
+        let inputs = [(70_000, tweak(2), tweak(3)), (30_000, tweak(4), tweak(5))];
+        let outputs = [(50_000, tweak(6), tweak(7)), (10_000, tweak(8), tweak(9))];
+        let vbf = super::last_value_blinding_factor(&secp, &inputs, &outputs, 40_000, tweak(10));
+        let last = (40_000, tweak(10), vbf);
+        let in_commits: Vec<_> = inputs.iter().map(&commit).collect();
+        let mut out_commits: Vec<_> = outputs.iter().map(&commit).collect();
+        out_commits.push(commit(&last));
+        assert!(secp256k1_zkp::verify_commitments_sum_to_equal(
+            &secp,
+            &in_commits,
+            &out_commits
+        ));
+
+        // Any other blinder breaks the balance
+        out_commits.pop();
+        out_commits.push(commit(&(40_000, tweak(10), tweak(11))));
+        assert!(!secp256k1_zkp::verify_commitments_sum_to_equal(
+            &secp,
+            &in_commits,
+            &out_commits
+        ));
+    }
+
+    #[test]
+    fn issuance_ids() {
+        use elements::hashes::Hash;
+        use std::str::FromStr;
+
+        // Issuance from Elements Core's test vectors
+        let prevout = OutPoint::from_str(
+            "05a047c98e82a848dee94efcf32462b065198bebf2404d201ba2e06db30b28f4:0",
+        )
+        .unwrap();
+        let contract_hash = ContractHash::from_inner([0; 32]);
+        assert_eq!(
+            compute_asset_id(prevout, contract_hash).to_string(),
+            "dcd60818d863b5c026c40b2bc3ba6fdaf5018bcc8606c18adf7db4da0bcd8533"
+        );
+        assert_eq!(
+            compute_reissuance_token_id(prevout, contract_hash, false).to_string(),
+            "c1adb114f4f87d33bf9ce90dd4f9ca523dd414d6cd010a7917903e2009689530"
+        );
+        assert_eq!(
+            compute_reissuance_token_id(prevout, contract_hash, true).to_string(),
+            "d08425cac1a728360ae7c8aad2b21e9a04d1ab1c09959562661e5f13d9c5f803"
+        );
+    }
+}